@@ -0,0 +1,15 @@
+//! Per-event dispatch cost of `Backend = Match;`. See `common` for the
+//! compile times of both backends.
+
+// ring_machine! pairs up its 150 states one recursion at a time
+#![recursion_limit = "256"]
+
+#[macro_use]
+extern crate fsm_rs;
+
+#[macro_use]
+mod common;
+
+ring_machine!(Backend = Match;);
+
+bench_dispatch!("dispatch/match");
@@ -1,5 +1,5 @@
 #[macro_use]
-extern crate machine;
+extern crate fsm_rs;
 
 machine!(
     #[derive(Clone, Debug, PartialEq)]
@@ -95,7 +95,7 @@ fn main() {
     t = t.on_advance(Advance);
     assert_eq!(t, Traffic::green(0));
     t = t.on_pass_car(PassCar { count: 5 });
-    assert_eq!(t, Traffic::green(3));
+    assert_eq!(t, Traffic::green(5));
     t = t.on_pass_car(PassCar { count: 7 });
     assert_eq!(t, Traffic::orange());
     t = t.on_advance(Advance);
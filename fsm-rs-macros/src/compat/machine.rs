@@ -0,0 +1,167 @@
+use heck::SnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream, Result},
+    Fields, ItemEnum,
+};
+
+/// `machine!` input: an enum whose variants describe the states.
+#[derive(Debug, PartialEq)]
+pub(crate) struct MachineEnum(ItemEnum);
+
+impl Parse for MachineEnum {
+    /// example machine:
+    ///
+    /// ```text
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// enum Traffic {
+    ///     Green { count: u8 },
+    ///     Orange,
+    ///     Red,
+    /// }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let item: ItemEnum = input.parse()?;
+
+        for variant in &item.variants {
+            if let Fields::Unnamed(_) = variant.fields {
+                return Err(syn::Error::new(
+                    variant.ident.span(),
+                    "states must be unit or struct-like variants",
+                ));
+            }
+
+            if variant.ident == "Error" {
+                return Err(syn::Error::new(
+                    variant.ident.span(),
+                    "`Error` is reserved for the error state",
+                ));
+            }
+        }
+
+        Ok(MachineEnum(item))
+    }
+}
+
+impl ToTokens for MachineEnum {
+    /// Generates one struct per state, the public enum wrapping them plus an
+    /// `Error` state, and a snake_case constructor per variant.
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let attrs = &self.0.attrs;
+        let name = &self.0.ident;
+
+        let states: Vec<_> = self.0.variants.iter().map(|v| &v.ident).collect();
+
+        let structs = self.0.variants.iter().map(|v| {
+            let state = &v.ident;
+            let fields = v.fields.iter();
+            quote! {
+                #( #attrs )*
+                pub struct #state {
+                    #( #fields ),*
+                }
+            }
+        });
+
+        let constructors = self.0.variants.iter().map(|v| {
+            let state = &v.ident;
+            let constructor = format_ident!("{}", state.to_string().to_snake_case());
+            let fields: Vec<_> = v.fields.iter().map(|v| &v.ident).collect();
+            let types = v.fields.iter().map(|v| &v.ty);
+            quote! {
+                pub fn #constructor(#( #fields: #types ),*) -> #name {
+                    #name::#state(#state { #( #fields ),* })
+                }
+            }
+        });
+
+        tokens.extend(quote! {
+            #( #attrs )*
+            pub enum #name {
+                Error,
+                #( #states(#states) ),*
+            }
+
+            #( #structs )*
+
+            impl #name {
+                #( #constructors )*
+
+                pub fn error() -> #name {
+                    #name::Error
+                }
+
+                pub fn is_error(&self) -> bool {
+                    matches!(self, #name::Error)
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn test_machine_enum_parse_and_to_tokens() {
+        let machine: MachineEnum = syn::parse2(quote! {
+            #[derive(Clone)]
+            enum Traffic {
+                Green { count: u8 },
+                Red,
+            }
+        })
+        .unwrap();
+
+        let left = quote! {
+            #[derive(Clone)]
+            pub enum Traffic {
+                Error,
+                Green(Green),
+                Red(Red)
+            }
+
+            #[derive(Clone)]
+            pub struct Green {
+                count: u8
+            }
+
+            #[derive(Clone)]
+            pub struct Red {
+            }
+
+            impl Traffic {
+                pub fn green(count: u8) -> Traffic {
+                    Traffic::Green(Green { count })
+                }
+
+                pub fn red() -> Traffic {
+                    Traffic::Red(Red {})
+                }
+
+                pub fn error() -> Traffic {
+                    Traffic::Error
+                }
+
+                pub fn is_error(&self) -> bool {
+                    matches!(self, Traffic::Error)
+                }
+            }
+        };
+
+        let mut right = TokenStream::new();
+        machine.to_tokens(&mut right);
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+
+        assert!(syn::parse2::<MachineEnum>(quote! {
+            enum Traffic {
+                Green(u8),
+            }
+        })
+        .is_err());
+    }
+}
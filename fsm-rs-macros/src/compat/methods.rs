@@ -0,0 +1,43 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream, Result},
+    Ident, Token,
+};
+
+use crate::fsm::methods::Methods;
+
+/// `methods!` input: the machine enum and its per-state methods.
+#[derive(Debug, PartialEq)]
+pub(crate) struct MethodsDecl {
+    pub enum_name: Ident,
+    pub methods: Methods,
+}
+
+impl Parse for MethodsDecl {
+    /// example methods:
+    ///
+    /// ```text
+    /// Traffic, [
+    ///     Green => get count: u8,
+    ///     Green, Orange, Red => fn can_pass(&self) -> bool
+    /// ]
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let enum_name: Ident = input.parse()?;
+        let _: Token![,] = input.parse()?;
+
+        let content;
+        bracketed!(content in input);
+        let methods = Methods::parse_list(&content)?;
+
+        Ok(MethodsDecl { enum_name, methods })
+    }
+}
+
+impl ToTokens for MethodsDecl {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(self.methods.to_impl_tokens(&self.enum_name));
+    }
+}
@@ -0,0 +1,9 @@
+//! Front-end for the `machine!`, `transitions!` and `methods!` macros of the
+//! [machine] crate. The `machine!` enum is parsed here, while `transitions!`
+//! and `methods!` reuse the `fsm` transition and method parsers.
+//!
+//! [machine]: https://docs.rs/machine
+
+pub mod machine;
+pub mod methods;
+pub mod transitions;
@@ -0,0 +1,200 @@
+use heck::SnakeCase;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream, Result},
+    punctuated::Punctuated,
+    GenericArgument, Ident, PathArguments, Token, Type,
+};
+
+use crate::fsm::transitions::{Transition, TransitionRow};
+
+/// `transitions!` input: the machine enum and its `(State, Event) => Target`
+/// rows, grouped per event type.
+#[derive(Debug, PartialEq)]
+pub(crate) struct TransitionTable {
+    pub enum_name: Ident,
+    pub events: Vec<(Type, Transition)>,
+}
+
+impl Parse for TransitionTable {
+    /// example transition table:
+    ///
+    /// ```text
+    /// Traffic, [
+    ///     (Green, Advance) => Orange,
+    ///     (Orange, Advance) => Red,
+    ///     (Green, PassCar) => [Green, Orange]
+    /// ]
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let enum_name: Ident = input.parse()?;
+        let _: Token![,] = input.parse()?;
+
+        let content;
+        bracketed!(content in input);
+        let rows: Punctuated<TransitionRow<Type>, Token![,]> =
+            content.parse_terminated(TransitionRow::parse)?;
+
+        let mut events: Vec<(Type, Transition)> = Vec::new();
+        for row in rows {
+            for pair in &row.pairs {
+                if pair.internal || pair.from.is_empty() {
+                    return Err(syn::Error::new(
+                        pair.to.span(),
+                        "internal and wildcard transitions are only supported by fsm!",
+                    ));
+                }
+            }
+
+            let index = match events.iter().position(|v| v.0 == row.event) {
+                Some(index) => index,
+                None => {
+                    let method = handler_name(&row.event)?;
+                    events.push((row.event, Transition::new(method)));
+                    events.len() - 1
+                }
+            };
+            for pair in row.pairs {
+                events[index].1.add(pair)?;
+            }
+        }
+
+        Ok(TransitionTable { enum_name, events })
+    }
+}
+
+/// `on_pass_car` for `path::PassCar<'a, T>`
+fn handler_name(event: &Type) -> Result<Ident> {
+    match event {
+        Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last().unwrap();
+            Ok(format_ident!(
+                "on_{}",
+                segment.ident.to_string().to_snake_case()
+            ))
+        }
+        _ => Err(syn::Error::new_spanned(
+            event,
+            "expected an event type path",
+        )),
+    }
+}
+
+/// Generic parameters of the handler for `event`: its lifetimes, and every
+/// type argument that is a bare identifier, e.g. `<'a, T>` for
+/// `PassCar<'a, T>`.
+fn handler_generics(event: &Type) -> TokenStream {
+    let arguments = match event {
+        Type::Path(path) => &path.path.segments.last().unwrap().arguments,
+        _ => return TokenStream::new(),
+    };
+
+    let params: Vec<_> = match arguments {
+        PathArguments::AngleBracketed(arguments) => arguments
+            .args
+            .iter()
+            .filter_map(|v| match v {
+                GenericArgument::Lifetime(lifetime) => Some(quote!(#lifetime)),
+                GenericArgument::Type(Type::Path(path))
+                    if path.qself.is_none() && path.path.get_ident().is_some() =>
+                {
+                    Some(quote!(#path))
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    if params.is_empty() {
+        TokenStream::new()
+    } else {
+        quote!(<#( #params ),*>)
+    }
+}
+
+impl ToTokens for TransitionTable {
+    /// Generates one `on_<event>` method per event type. A state with a
+    /// single target returns that target's struct from its handler, one with
+    /// several returns the machine enum itself. Any other state moves to
+    /// `Error`.
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let enum_name = &self.enum_name;
+
+        let handlers = self.events.iter().map(|(event, transition)| {
+            let method = &transition.event_name;
+            let generics = handler_generics(event);
+
+            let cases = transition.pairs.iter().map(|(from, tos)| {
+                if tos.len() == 1 {
+                    let to = tos.iter().next().unwrap();
+                    quote! {
+                        #enum_name::#from(state) => #enum_name::#to(state.#method(input)),
+                    }
+                } else {
+                    quote! {
+                        #enum_name::#from(state) => state.#method(input),
+                    }
+                }
+            });
+
+            quote! {
+                pub fn #method #generics(self, input: #event) -> #enum_name {
+                    match self {
+                        #( #cases )*
+                        _ => #enum_name::Error,
+                    }
+                }
+            }
+        });
+
+        tokens.extend(quote! {
+            impl #enum_name {
+                #( #handlers )*
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn test_transition_table_parse_and_to_tokens() {
+        let table: TransitionTable = syn::parse2(quote! {
+            Traffic, [
+                (Green, prefix::Advance) => Orange,
+                (Orange, prefix::Advance) => Green,
+                (Green, PassCar<'a, T>) => [Green, Orange]
+            ]
+        })
+        .unwrap();
+
+        let left = quote! {
+            impl Traffic {
+                pub fn on_advance(self, input: prefix::Advance) -> Traffic {
+                    match self {
+                        Traffic::Green(state) => Traffic::Orange(state.on_advance(input)),
+                        Traffic::Orange(state) => Traffic::Green(state.on_advance(input)),
+                        _ => Traffic::Error,
+                    }
+                }
+                pub fn on_pass_car<'a, T>(self, input: PassCar<'a, T>) -> Traffic {
+                    match self {
+                        Traffic::Green(state) => state.on_pass_car(input),
+                        _ => Traffic::Error,
+                    }
+                }
+            }
+        };
+
+        let mut right = TokenStream::new();
+        table.to_tokens(&mut right);
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+    }
+}
@@ -0,0 +1,451 @@
+use proc_macro2::TokenStream;
+use quote::{quote_spanned, ToTokens};
+use std::collections::{BTreeSet, VecDeque};
+use syn::{
+    parse::{Parse, ParseStream, Result},
+    punctuated::Punctuated,
+    Attribute, Ident, Token,
+};
+
+use crate::fsm::{
+    events::Events,
+    states::{FinalStates, States},
+    transitions::Transitions,
+};
+
+/// How a lint violation is reported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Lints {
+    /// states that cannot be reached from the initial state
+    pub unreachable_states: Level,
+    /// declared events that no transition uses
+    pub unused_events: Level,
+    /// non-final states without any outgoing transition
+    pub dead_end_states: Level,
+}
+
+impl Default for Lints {
+    fn default() -> Self {
+        Lints {
+            unreachable_states: Level::Deny,
+            unused_events: Level::Deny,
+            dead_end_states: Level::Deny,
+        }
+    }
+}
+
+impl Parse for Lints {
+    /// example lint attributes:
+    ///
+    /// ```text
+    /// #![warn(unreachable_states)]
+    /// #![allow(unused_events, dead_end_states)]
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut lints = Lints::default();
+
+        for attr in input.call(Attribute::parse_inner)? {
+            let level = if attr.path.is_ident("allow") {
+                Level::Allow
+            } else if attr.path.is_ident("warn") {
+                Level::Warn
+            } else if attr.path.is_ident("deny") {
+                Level::Deny
+            } else {
+                return Err(syn::Error::new_spanned(
+                    &attr.path,
+                    "expected #![allow(..)], #![warn(..)] or #![deny(..)]",
+                ));
+            };
+
+            let names: Punctuated<Ident, Token![,]> =
+                attr.parse_args_with(Punctuated::parse_terminated)?;
+
+            for name in names {
+                if name == "unreachable_states" {
+                    lints.unreachable_states = level;
+                } else if name == "unused_events" {
+                    lints.unused_events = level;
+                } else if name == "dead_end_states" {
+                    lints.dead_end_states = level;
+                } else {
+                    return Err(syn::Error::new(name.span(), "unknown lint"));
+                }
+            }
+        }
+
+        Ok(lints)
+    }
+}
+
+/// A lint reported at `Warn` level. Proc macros cannot emit warnings on
+/// stable, so it expands to a use of a deprecated constant spanned on the
+/// offending declaration.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Warning {
+    pub lint: &'static str,
+    pub at: Ident,
+    pub message: String,
+}
+
+impl ToTokens for Warning {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let lint = Ident::new(self.lint, self.at.span());
+        let message = &self.message;
+        tokens.extend(quote_spanned! {self.at.span()=>
+            const _: () = {
+                #[deprecated(note = #message)]
+                #[allow(non_upper_case_globals)]
+                const #lint: () = ();
+                #lint
+            };
+        });
+    }
+}
+
+/// Collects lint violations into errors and warnings.
+struct Reporter {
+    error: Option<syn::Error>,
+    warnings: Vec<Warning>,
+}
+
+impl Reporter {
+    fn report(&mut self, level: Level, lint: &'static str, at: &Ident, message: String) {
+        match level {
+            Level::Allow => {}
+            Level::Warn => self.warnings.push(Warning {
+                lint,
+                at: at.clone(),
+                message,
+            }),
+            Level::Deny => self.error(at, message),
+        }
+    }
+
+    fn error(&mut self, at: &Ident, message: String) {
+        let error = syn::Error::new(at.span(), message);
+        match &mut self.error {
+            Some(v) => v.combine(error),
+            None => self.error = Some(error),
+        }
+    }
+}
+
+/// Check the transition graph against `lints`. Denied violations are
+/// returned as one combined error, warned ones as `Warning`s to expand.
+pub(crate) fn check(
+    lints: &Lints,
+    states: &States,
+    finals: &FinalStates,
+    events: &Events,
+    transitions: &Transitions,
+) -> Result<Vec<Warning>> {
+    let mut reporter = Reporter {
+        error: None,
+        warnings: Vec::new(),
+    };
+
+    let initial = &states.initial().state_name;
+
+    for name in &finals.0 {
+        if states.get(name).is_none() {
+            reporter.error(
+                name,
+                format!("final state `{}` is not declared in States", name),
+            );
+        } else if transitions.0.iter().any(|v| v.pairs.contains_key(name)) {
+            reporter.error(
+                name,
+                format!("final state `{}` cannot have outgoing transitions", name),
+            );
+        }
+    }
+
+    for transition in &transitions.0 {
+        let name = &transition.event_name;
+        if events.get(name).is_none() {
+            reporter.error(name, format!("event `{}` is not declared in Events", name));
+        }
+    }
+
+    // breadth-first walk from the initial state
+    let mut reached: BTreeSet<&Ident> = BTreeSet::new();
+    let mut queue: VecDeque<&Ident> = VecDeque::new();
+    let _ = reached.insert(initial);
+    queue.push_back(initial);
+    while let Some(from) = queue.pop_front() {
+        let tos = transitions
+            .0
+            .iter()
+            .flat_map(|v| v.pairs.get(from))
+            .flatten();
+        // a failed entry() moves on to the on_error target
+        for to in tos.chain(transitions.1.get(from)) {
+            if reached.insert(to) {
+                queue.push_back(to);
+            }
+        }
+    }
+
+    for state in &states.0 {
+        let name = &state.state_name;
+
+        if !reached.contains(name) {
+            reporter.report(
+                lints.unreachable_states,
+                "unreachable_states",
+                name,
+                format!(
+                    "state `{}` is unreachable from the initial state `{}`",
+                    name, initial
+                ),
+            );
+        }
+
+        if !finals.contains(name) && transitions.0.iter().all(|v| !v.pairs.contains_key(name)) {
+            reporter.report(
+                lints.dead_end_states,
+                "dead_end_states",
+                name,
+                format!("state `{}` has no outgoing transitions", name),
+            );
+        }
+    }
+
+    for event in &events.0 {
+        let name = &event.event_name;
+
+        if transitions.0.iter().all(|v| v.event_name != *name) {
+            reporter.report(
+                lints.unused_events,
+                "unused_events",
+                name,
+                format!("event `{}` is not used by any transition", name),
+            );
+        }
+    }
+
+    match reporter.error {
+        Some(error) => Err(error),
+        None => Ok(reporter.warnings),
+    }
+}
+
+/// Checks for `Deterministic;` machines: every (state, event) pair has a
+/// single target, and no transition line is repeated.
+pub(crate) fn check_deterministic(transitions: &Transitions) -> Result<()> {
+    let mut reporter = Reporter {
+        error: None,
+        warnings: Vec::new(),
+    };
+
+    for transition in &transitions.0 {
+        let event_name = &transition.event_name;
+
+        for duplicate in &transition.duplicates {
+            reporter.error(
+                duplicate,
+                format!(
+                    "duplicate transition to `{}` on `{}`",
+                    duplicate, event_name
+                ),
+            );
+        }
+
+        for (from, tos) in &transition.pairs {
+            // several targets would leave the choice to the on_<event> handler
+            // returning the `<From><Event>Target` enum at runtime
+            for to in tos.iter().skip(1) {
+                reporter.error(
+                    to,
+                    format!(
+                        "`{}` already has a target on `{}`, `{}` makes the machine non-deterministic",
+                        from, event_name, to
+                    ),
+                );
+            }
+        }
+    }
+
+    match reporter.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn parse_and_check(lints: TokenStream, finals: TokenStream) -> Result<Vec<Warning>> {
+        let lints: Lints = syn::parse2(lints)?;
+        let finals: FinalStates = syn::parse2(finals)?;
+        let states: States = syn::parse2(quote! {
+            States {
+                S1 = S1,
+                S2 = S2,
+                S3 = S3
+            }
+        })?;
+        let events: Events = syn::parse2(quote! {
+            Events {
+                E1 = E1,
+                E2 = E2
+            }
+        })?;
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [
+                    S1 => S2,
+                    S2 => S1,
+                ]
+            }
+        })?;
+
+        check(&lints, &states, &finals, &events, &transitions)
+    }
+
+    #[test]
+    fn test_check_lint_levels() {
+        let error = parse_and_check(quote!(), quote!(Final {})).unwrap_err();
+        let messages: Vec<_> = error.into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "state `S3` is unreachable from the initial state `S1`",
+                "state `S3` has no outgoing transitions",
+                "event `E2` is not used by any transition",
+            ]
+        );
+
+        let warnings = parse_and_check(
+            quote! {
+                #![warn(unreachable_states, dead_end_states)]
+                #![allow(unused_events)]
+            },
+            quote!(Final {}),
+        )
+        .unwrap();
+        let lints: Vec<_> = warnings.iter().map(|v| v.lint).collect();
+        assert_eq!(lints, vec!["unreachable_states", "dead_end_states"]);
+
+        assert!(parse_and_check(quote!(#![allow(unknown_lint)]), quote!(Final {})).is_err());
+    }
+
+    #[test]
+    fn test_check_final_states() {
+        let warnings = parse_and_check(
+            quote!(#![allow(unreachable_states, unused_events)]),
+            quote! {
+                Final { S3 }
+            },
+        );
+        assert!(warnings.unwrap().is_empty());
+
+        let error = parse_and_check(
+            quote!(#![allow(unreachable_states, unused_events)]),
+            quote! {
+                Final { S2, S3, S4 }
+            },
+        )
+        .unwrap_err();
+        let messages: Vec<_> = error.into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "final state `S2` cannot have outgoing transitions",
+                "final state `S4` is not declared in States",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_undeclared_events() {
+        let states: States = syn::parse2(quote!(States { S1 = S1, S2 = S2 })).unwrap();
+        let events: Events = syn::parse2(quote!(Events { E1 = E1 })).unwrap();
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [ S1 => S2 ],
+                E2 [ S2 => S1 ]
+            }
+        })
+        .unwrap();
+
+        let error = check(
+            &Lints::default(),
+            &states,
+            &FinalStates::default(),
+            &events,
+            &transitions,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "event `E2` is not declared in Events");
+    }
+
+    #[test]
+    fn test_check_error_routes() {
+        let states: States = syn::parse2(quote! {
+            States {
+                S1 = S1,
+                S2 = S2,
+                Failed = Failed
+            }
+        })
+        .unwrap();
+        let finals: FinalStates = syn::parse2(quote!(Final { Failed })).unwrap();
+        let events: Events = syn::parse2(quote!(Events { E1 = E1 })).unwrap();
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [ S1 => S2, S2 => S1 ],
+                S2 on_error => Failed,
+            }
+        })
+        .unwrap();
+
+        let warnings = check(&Lints::default(), &states, &finals, &events, &transitions);
+        assert!(warnings.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_deterministic() {
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [
+                    S1 => S2,
+                    S2 => S1,
+                ]
+            }
+        })
+        .unwrap();
+        assert!(check_deterministic(&transitions).is_ok());
+
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [
+                    S1 => S2,
+                    S1 => S2,
+                    S2 => S1,
+                    S2 => S3,
+                ]
+            }
+        })
+        .unwrap();
+        let error = check_deterministic(&transitions).unwrap_err();
+        let messages: Vec<_> = error.into_iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "duplicate transition to `S2` on `E1`",
+                "`S2` already has a target on `E1`, `S3` makes the machine non-deterministic",
+            ]
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream, Result},
+    punctuated::Punctuated,
+    Ident, Token, Type,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Event {
+    pub event_name: Ident,
+    pub event_type: Type,
+}
+
+impl Parse for Event {
+    /// example event:
+    ///
+    /// ```text
+    /// S1 = S1
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // S1 = S1
+        // __
+        let event_name: Ident = Ident::parse(input)?;
+
+        // S1 = S1
+        //    _
+        let _: Token![=] = input.parse()?;
+
+        // S1 = S1
+        //      __
+        let event_type: Type = Type::parse(input)?;
+
+        Ok(Event {
+            event_name,
+            event_type,
+        })
+    }
+}
+
+impl ToTokens for Event {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let event_name = &self.event_name;
+        let event_type = &self.event_type;
+        tokens.extend(quote!(
+            #event_name(#event_type)
+        ));
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Events(pub Vec<Event>);
+
+impl Parse for Events {
+    /// example events:
+    ///
+    /// ```text
+    /// Events {
+    ///     S1 = S1,
+    ///     S2 = S2,
+    ///     S3 = S3,
+    ///     S4 = S4,
+    ///     S5 = S5
+    /// }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // Events { ... }
+        // --------------
+        let events_magic = Ident::parse(input)?;
+
+        if events_magic != "Events" {
+            return Err(input.error("expected Events { ... }"));
+        }
+
+        let content;
+        braced!(content in input);
+
+        let events: Punctuated<Event, Token![,]> = content.parse_terminated(Event::parse)?;
+        Ok(Events(events.into_iter().collect()))
+    }
+}
+
+impl Events {
+    pub fn get(&self, name: &Ident) -> Option<&Event> {
+        self.0.iter().find(|v| v.event_name == *name)
+    }
+}
+
+impl ToTokens for Events {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let events = &self.0;
+        let names: Vec<_> = events.iter().map(|v| &v.event_name).collect();
+        let name_strs: Vec<_> = names.iter().map(|v| v.to_string()).collect();
+        let count = names.len();
+        tokens.extend(quote!(
+            #[derive(Clone, Debug, PartialEq)]
+            pub enum Event {
+                #(#events),*
+            }
+
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+            pub enum EventId {
+                #(#names),*
+            }
+
+            impl Event {
+                pub fn id(&self) -> EventId {
+                    match self {
+                        #(Event::#names(_) => EventId::#names),*
+                    }
+                }
+            }
+
+            impl EventId {
+                pub const ALL: [EventId; #count] = [#(EventId::#names),*];
+
+                pub fn name(self) -> &'static str {
+                    match self {
+                        #(EventId::#names => #name_strs),*
+                    }
+                }
+            }
+
+            impl ::fsm_rs::Id for EventId {
+                fn all() -> &'static [EventId] {
+                    &EventId::ALL
+                }
+
+                fn name(self) -> &'static str {
+                    EventId::name(self)
+                }
+            }
+
+            impl ::core::fmt::Display for EventId {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str(self.name())
+                }
+            }
+
+            /// Returned when parsing a string that names no event.
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub struct ParseEventIdError;
+
+            impl ::core::fmt::Display for ParseEventIdError {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str("unknown event")
+                }
+            }
+
+            impl ::core::str::FromStr for EventId {
+                type Err = ParseEventIdError;
+
+                fn from_str(s: &str) -> Result<EventId, ParseEventIdError> {
+                    match s {
+                        #(#name_strs => Ok(EventId::#names),)*
+                        _ => Err(ParseEventIdError),
+                    }
+                }
+            }
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::TokenStream;
+    use syn;
+
+    #[test]
+    fn test_events_parse_and_to_tokens() {
+        let events: Events = syn::parse2(quote! {
+            Events {
+                E1 = E1
+            }
+        })
+        .unwrap();
+
+        let left = quote! {
+            #[derive(Clone, Debug, PartialEq)]
+            pub enum Event {
+                E1(E1)
+            }
+
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+            pub enum EventId {
+                E1
+            }
+
+            impl Event {
+                pub fn id(&self) -> EventId {
+                    match self {
+                        Event::E1(_) => EventId::E1
+                    }
+                }
+            }
+
+            impl EventId {
+                pub const ALL: [EventId; 1usize] = [EventId::E1];
+
+                pub fn name(self) -> &'static str {
+                    match self {
+                        EventId::E1 => "E1"
+                    }
+                }
+            }
+
+            impl ::fsm_rs::Id for EventId {
+                fn all() -> &'static [EventId] {
+                    &EventId::ALL
+                }
+
+                fn name(self) -> &'static str {
+                    EventId::name(self)
+                }
+            }
+
+            impl ::core::fmt::Display for EventId {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str(self.name())
+                }
+            }
+
+            /// Returned when parsing a string that names no event.
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub struct ParseEventIdError;
+
+            impl ::core::fmt::Display for ParseEventIdError {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str("unknown event")
+                }
+            }
+
+            impl ::core::str::FromStr for EventId {
+                type Err = ParseEventIdError;
+
+                fn from_str(s: &str) -> Result<EventId, ParseEventIdError> {
+                    match s {
+                        "E1" => Ok(EventId::E1),
+                        _ => Err(ParseEventIdError),
+                    }
+                }
+            }
+        };
+
+        let mut right = TokenStream::new();
+        events.to_tokens(&mut right);
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+}
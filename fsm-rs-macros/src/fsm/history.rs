@@ -0,0 +1,134 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream, Result},
+    Ident, LitInt, Token,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct History {
+    capacity: LitInt,
+}
+
+impl Parse for History {
+    /// example history setting:
+    ///
+    /// ```text
+    /// History = 32;
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // History = 32;
+        // _______
+        let magic: Ident = Ident::parse(input)?;
+
+        if magic != "History" {
+            return Err(syn::Error::new(magic.span(), "expected History = N;"));
+        }
+
+        // History = 32;
+        //         _
+        let _: Token![=] = input.parse()?;
+
+        // History = 32;
+        //           __
+        let capacity: LitInt = input.parse()?;
+
+        if capacity.base10_parse::<usize>()? == 0 {
+            return Err(syn::Error::new(
+                capacity.span(),
+                "history capacity must be at least 1",
+            ));
+        }
+
+        // History = 32;
+        //             _
+        let _: Token![;] = input.parse()?;
+
+        Ok(History { capacity })
+    }
+}
+
+impl ToTokens for History {
+    /// Generates a fixed-size ring buffer of transition records. Only `core`
+    /// is used, so it works in `no_std` crates.
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let capacity = &self.capacity;
+        tokens.extend(quote! {
+            /// One transition taken by `Machine`.
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub struct TransitionRecord {
+                pub from: StateId,
+                pub event: EventId,
+                pub to: StateId,
+                /// Position of this transition since the machine was created.
+                pub sequence: u64,
+            }
+
+            /// The last transitions taken by `Machine`, oldest first.
+            #[derive(Clone, Debug)]
+            pub struct History {
+                records: [Option<TransitionRecord>; #capacity],
+                next: usize,
+                sequence: u64,
+            }
+
+            impl History {
+                pub const CAPACITY: usize = #capacity;
+
+                const fn new() -> History {
+                    History {
+                        records: [None; #capacity],
+                        next: 0,
+                        sequence: 0,
+                    }
+                }
+
+                fn push(&mut self, from: StateId, event: EventId, to: StateId) {
+                    self.records[self.next] = Some(TransitionRecord {
+                        from,
+                        event,
+                        to,
+                        sequence: self.sequence,
+                    });
+                    self.next = (self.next + 1) % Self::CAPACITY;
+                    self.sequence += 1;
+                }
+
+                pub fn len(&self) -> usize {
+                    ::core::cmp::min(self.sequence, Self::CAPACITY as u64) as usize
+                }
+
+                pub fn is_empty(&self) -> bool {
+                    self.sequence == 0
+                }
+
+                pub fn iter(&self) -> impl Iterator<Item = &TransitionRecord> {
+                    self.records[self.next..]
+                        .iter()
+                        .chain(self.records[..self.next].iter())
+                        .filter_map(Option::as_ref)
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn test_history_parse() {
+        let history: History = syn::parse2(quote! {
+            History = 32;
+        })
+        .unwrap();
+        assert_eq!(history.capacity.base10_parse::<usize>().unwrap(), 32);
+
+        assert!(syn::parse2::<History>(quote! {
+            History = 0;
+        })
+        .is_err());
+    }
+}
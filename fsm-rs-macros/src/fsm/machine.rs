@@ -0,0 +1,656 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream, Result},
+    Ident,
+};
+
+use crate::fsm::{
+    analysis::{self, Lints, Warning},
+    events::Events,
+    machine_context::MachineContext,
+    methods::Methods,
+    settings::{Backend, Dispatch, Mode, Settings},
+    states::{FinalStates, States},
+    transitions::{self, Transitions},
+    typestate,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Machine {
+    pub lints: Lints,
+    pub warnings: Vec<Warning>,
+    pub machine_context: MachineContext,
+    pub settings: Settings,
+    pub events: Events,
+    pub states: States,
+    pub finals: FinalStates,
+    pub transitions: Transitions,
+    pub methods: Methods,
+}
+
+impl Parse for Machine {
+    /// example machine:
+    ///
+    /// ```text
+    ///
+    /// #![allow(dead_end_states)]
+    ///
+    /// Context = Machine;
+    ///
+    /// History = 32;
+    ///
+    /// States {
+    ///     S1 = S1,
+    ///     S2 = S2,
+    ///     S3 = S3,
+    ///     S4 = S4,
+    ///     S5 = S5
+    /// }
+    ///
+    /// Final { S3, S5 }
+    ///
+    /// Events {
+    ///     EVENT1 = Event1,
+    ///     EVENT2 = Event2
+    /// }
+    ///
+    /// Transitions {
+    ///     EVENT1 [
+    ///        S1 => S2,
+    ///        S1 => S3,
+    ///     ],
+    ///     EVENT2 [
+    ///         S4 => S5,
+    ///     ]
+    /// }
+    ///
+    /// Methods {
+    ///     S1, S2 => get count: u8
+    /// }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // #![allow(dead_end_states)]
+        let lints = Lints::parse(input)?;
+
+        // Context = Machine;
+        let machine_context = MachineContext::parse(input)?;
+
+        // History = 32;
+        let settings = Settings::parse(input)?;
+
+        // States {
+        //     S1 = S1,
+        //     S2 = S2,
+        //     S3 = S3,
+        //     S4 = S4,
+        //     S5 = S5
+        // }
+        let states = States::parse(input)?;
+
+        // Final { S3, S5 }
+        let finals = if input.peek(Ident) && input.fork().parse::<Ident>()? == "Final" {
+            FinalStates::parse(input)?
+        } else {
+            FinalStates::default()
+        };
+
+        // Events {
+        //     EVENT1 = Event1,
+        //     EVENT2 = Event2
+        // }
+        let events = Events::parse(input)?;
+
+        // Transitions {
+        //     EVENT1 [
+        //         S1 => S2,
+        //         S1 => S3,
+        //     ],
+        //     EVENT2 [
+        //         S4 => S5,
+        //     ],
+        // }
+        let mut transitions = Transitions::parse(input)?;
+        transitions.expand_wildcards(&states, &finals);
+        if let Some(error_state) = &settings.error_state {
+            transitions.expand_error_state(&states, error_state);
+        }
+
+        // Methods {
+        //     S1, S2 => get count: u8
+        // }
+        let methods = if input.is_empty() {
+            Methods::default()
+        } else {
+            Methods::parse(input)?
+        };
+
+        let paired = transitions.0.iter().flat_map(|v| {
+            v.pairs
+                .iter()
+                .flat_map(|(from, tos)| Some(from).into_iter().chain(tos))
+                .chain(&v.wildcard)
+        });
+        let routed = transitions.1.iter().flat_map(|(from, to)| vec![from, to]);
+        for name in paired.chain(routed).chain(&settings.error_state) {
+            if states.get(name).is_none() {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("state `{}` is not declared in States", name),
+                ));
+            }
+        }
+
+        for name in methods.0.iter().flat_map(|v| &v.states) {
+            if states.get(name).is_none() {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("state `{}` is not declared in States", name),
+                ));
+            }
+        }
+
+        let warnings = analysis::check(&lints, &states, &finals, &events, &transitions)?;
+
+        if settings.deterministic {
+            analysis::check_deterministic(&transitions)?;
+        }
+
+        if settings.mode == Mode::Typestate {
+            typestate::check(&states, settings.history.is_some())?;
+
+            if settings.backend != Backend::Match {
+                return Err(syn::Error::new(
+                    states.initial().state_name.span(),
+                    "Backend only applies to Mode = Match",
+                ));
+            }
+
+            if settings.transactional {
+                return Err(syn::Error::new(
+                    states.initial().state_name.span(),
+                    "Transactional only applies to Mode = Match",
+                ));
+            }
+
+            if settings.dispatch != Dispatch::Exit {
+                return Err(syn::Error::new(
+                    states.initial().state_name.span(),
+                    "Dispatch only applies to Mode = Match, typestate transitions always use handlers",
+                ));
+            }
+
+            if let Some(from) = transitions.1.keys().next() {
+                return Err(syn::Error::new(
+                    from.span(),
+                    "on_error and ErrorState only apply to Mode = Match",
+                ));
+            }
+        }
+
+        if settings.dispatch == Dispatch::Handler && settings.backend != Backend::Match {
+            return Err(syn::Error::new(
+                states.initial().state_name.span(),
+                "Dispatch = Handler requires Backend = Match",
+            ));
+        }
+
+        if let (Some(output), true) = (&settings.output, finals.0.is_empty()) {
+            return Err(syn::Error::new_spanned(
+                output,
+                "Output requires at least one state in Final { ... }",
+            ));
+        }
+
+        Ok(Machine {
+            lints,
+            warnings,
+            machine_context,
+            settings,
+            events,
+            states,
+            finals,
+            transitions,
+            methods,
+        })
+    }
+}
+
+impl Machine {
+    /// The summary returned by `StateMachine::description()`, in the syntax
+    /// of the declaration.
+    fn description(&self) -> String {
+        let states: Vec<_> = self
+            .states
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let name = &v.state_name;
+                if i == 0 {
+                    format!("{} (initial)", name)
+                } else if self.finals.contains(name) {
+                    format!("{} (final)", name)
+                } else {
+                    name.to_string()
+                }
+            })
+            .collect();
+        let events: Vec<_> = self
+            .events
+            .0
+            .iter()
+            .map(|v| v.event_name.to_string())
+            .collect();
+
+        let context_type = self.machine_context.context_type();
+        let mut description = format!(
+            "{}\nstates: {}\nevents: {}\ntransitions:",
+            quote!(#context_type),
+            states.join(", "),
+            events.join(", ")
+        );
+        for transition in &self.transitions.0 {
+            for (from, tos) in &transition.pairs {
+                let arrow = if transition.internal.contains(from) {
+                    "~>"
+                } else {
+                    "=>"
+                };
+                for to in tos {
+                    description.push_str(&format!(
+                        "\n    {}: {} {} {}",
+                        transition.event_name, from, arrow, to
+                    ));
+                }
+            }
+        }
+
+        description
+    }
+}
+
+impl ToTokens for Machine {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let states = &self.states;
+        let events = &self.events;
+        let warnings = &self.warnings;
+        let initial_state = &self.states.initial().state_name;
+        let finals = &self.finals.0;
+
+        let is_terminated = if finals.is_empty() {
+            quote!(false)
+        } else {
+            quote!(matches!(self.current_state, #( State::#finals(_) )|*))
+        };
+
+        let into_output_fn = match &self.settings.output {
+            Some(output) => quote! {
+                /// Consume the terminated machine and convert its final state
+                /// into the output. Returns the machine unchanged when it has
+                /// not terminated yet.
+                pub fn into_output(self) -> Result<#output, Self> {
+                    match self.current_state {
+                        #( State::#finals(state) => Ok(state.into()), )*
+                        _ => Err(self),
+                    }
+                }
+            },
+            None => TokenStream::new(),
+        };
+
+        let machine_context_type = &self.machine_context.context_type();
+
+        let machine_name = quote!(#machine_context_type).to_string();
+        let description = self.description();
+        let query_fns = self.transitions.to_query_fn_tokens();
+        let preview_fn = self.transitions.to_preview_fn_tokens(&self.settings);
+        let target_enums = self.transitions.to_target_enum_tokens(&self.states);
+        let enter_fn = self.transitions.to_enter_fn_tokens(&self.settings);
+        let event_fn_impl = match self.settings.backend {
+            Backend::Match => self
+                .transitions
+                .to_event_fn_tokens(&machine_name, &self.settings),
+            Backend::Table => self.transitions.to_table_event_fn_tokens(
+                &machine_name,
+                &self.settings,
+                &self.states,
+                &self.events,
+            ),
+        };
+
+        let transactional_event_fn = if self.settings.transactional {
+            transitions::transactional_event_fn(&self.settings)
+        } else {
+            TokenStream::new()
+        };
+
+        let (constness, context_init, state_init) = self
+            .settings
+            .constructor_tokens(machine_context_type, &self.states.initial().state_type);
+
+        let (history_def, history_field, history_init, history_fn) = match &self.settings.history {
+            Some(history) => (
+                quote!(#history),
+                quote!(history: History,),
+                quote!(history: History::new(),),
+                quote! {
+                    pub #constness fn history(&self) -> &History {
+                        &self.history
+                    }
+                },
+            ),
+            None => Default::default(),
+        };
+
+        let methods_impl = if self.methods.0.is_empty() {
+            TokenStream::new()
+        } else {
+            self.methods.to_impl_tokens(&format_ident!("State"))
+        };
+
+        if self.settings.mode == Mode::Typestate {
+            let machine = typestate::to_tokens(
+                machine_context_type,
+                states,
+                &self.finals,
+                events,
+                &self.transitions,
+                &self.settings,
+            );
+
+            tokens.extend(quote! {
+                #states
+
+                #events
+
+                #methods_impl
+
+                #( #warnings )*
+
+                #machine
+            });
+            return;
+        }
+
+        tokens.extend(quote! {
+            #[allow(non_snake_case)]
+
+            #states
+
+            #events
+
+            #methods_impl
+
+            #history_def
+
+            #target_enums
+
+            #( #warnings )*
+
+            pub struct Machine<O: ::fsm_rs::Observer<State, Event> = ()> {
+                context: #machine_context_type,
+                current_state: State,
+                observer: O,
+                #history_field
+            }
+
+            impl Machine {
+                pub #constness fn new() -> Machine {
+                    Machine::with_observer(())
+                }
+            }
+
+            impl<O: ::fsm_rs::Observer<State, Event>> Machine<O> {
+                #transactional_event_fn
+
+                #event_fn_impl
+
+                #enter_fn
+
+                pub #constness fn with_observer(observer: O) -> Machine<O> {
+                    Machine {
+                        context: #context_init,
+                        current_state: State::#initial_state(#state_init),
+                        observer,
+                        #history_init
+                    }
+                }
+
+                pub #constness fn state(&self) -> &State {
+                    &self.current_state
+                }
+
+                pub #constness fn context(&self) -> &#machine_context_type {
+                    &self.context
+                }
+
+                #query_fns
+
+                #preview_fn
+
+                pub #constness fn observer(&self) -> &O {
+                    &self.observer
+                }
+
+                /// Whether the machine is in one of its final states.
+                pub #constness fn is_terminated(&self) -> bool {
+                    #is_terminated
+                }
+
+                #into_output_fn
+
+                #history_fn
+            }
+
+            impl<O: ::fsm_rs::Observer<State, Event>> ::fsm_rs::StateMachine for Machine<O> {
+                type State = State;
+                type Event = Event;
+                type Context = #machine_context_type;
+                type Error = ::fsm_rs::TransitionError;
+
+                fn state(&self) -> &State {
+                    &self.current_state
+                }
+
+                fn handle(&mut self, event: Event) -> Result<bool, ::fsm_rs::TransitionError> {
+                    self.event(event)
+                }
+
+                fn can_handle(&self, event: &Event) -> bool {
+                    Machine::can_handle(self, event)
+                }
+
+                fn context(&self) -> &#machine_context_type {
+                    &self.context
+                }
+
+                fn description(&self) -> &'static str {
+                    #description
+                }
+
+                fn is_terminated(&self) -> bool {
+                    #is_terminated
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::TokenStream;
+    use syn::{self, parse_quote};
+
+    #[test]
+    fn test_machine_parse_and_to_tokens() {
+        let machine: Machine = syn::parse2(quote! {
+            Context = FSM;
+
+            States {
+                S1 = S1,
+                S2 = S2,
+                S3 = S3,
+                S4 = S4,
+                S5 = S5
+            }
+
+            Final { S5 }
+
+            Events {
+                EVENT1 = Event1,
+                EVENT2 = Event2
+            }
+
+            Transitions {
+                EVENT1 [
+                   S1 => S2,
+                   S1 => S3,
+                   S2 | S3 => S4,
+                ],
+                EVENT2 [
+                    S4 => S5,
+                ]
+            }
+        })
+        .unwrap();
+
+        assert!(machine.warnings.is_empty());
+        assert_eq!(machine.states.initial().state_name, "S1");
+        assert_eq!(machine.transitions.0.len(), 2);
+        assert_eq!(
+            machine.description(),
+            "FSM\n\
+             states: S1 (initial), S2, S3, S4, S5 (final)\n\
+             events: EVENT1, EVENT2\n\
+             transitions:\n    \
+             EVENT1: S1 => S2\n    \
+             EVENT1: S1 => S3\n    \
+             EVENT1: S2 => S4\n    \
+             EVENT1: S3 => S4\n    \
+             EVENT2: S4 => S5"
+        );
+
+        let mut tokens = TokenStream::new();
+        machine.to_tokens(&mut tokens);
+
+        let file: syn::File = syn::parse2(tokens).unwrap();
+        let item = |name: &str| {
+            file.items.iter().find(|v| match v {
+                syn::Item::Struct(v) => v.ident == name,
+                syn::Item::Impl(v) => {
+                    let self_ty = &v.self_ty;
+                    let trait_ = v.trait_.as_ref().map(|v| &v.1);
+                    quote!(#trait_ #self_ty).to_string() == name
+                }
+                _ => false,
+            })
+        };
+
+        let left: syn::Item = parse_quote! {
+            pub struct Machine<O: ::fsm_rs::Observer<State, Event> = ()> {
+                context: FSM,
+                current_state: State,
+                observer: O,
+            }
+        };
+        assert_eq!(Some(&left), item("Machine"));
+
+        let description = machine.description();
+        let left: syn::Item = parse_quote! {
+            impl<O: ::fsm_rs::Observer<State, Event>> ::fsm_rs::StateMachine for Machine<O> {
+                type State = State;
+                type Event = Event;
+                type Context = FSM;
+                type Error = ::fsm_rs::TransitionError;
+
+                fn state(&self) -> &State {
+                    &self.current_state
+                }
+
+                fn handle(&mut self, event: Event) -> Result<bool, ::fsm_rs::TransitionError> {
+                    self.event(event)
+                }
+
+                fn can_handle(&self, event: &Event) -> bool {
+                    Machine::can_handle(self, event)
+                }
+
+                fn context(&self) -> &FSM {
+                    &self.context
+                }
+
+                fn description(&self) -> &'static str {
+                    #description
+                }
+
+                fn is_terminated(&self) -> bool {
+                    matches!(self.current_state, State::S5(_))
+                }
+            }
+        };
+        assert_eq!(
+            Some(&left),
+            item(&quote!(::fsm_rs::StateMachine Machine<O>).to_string())
+        );
+    }
+
+    #[test]
+    fn test_machine_const_to_tokens() {
+        let machine: Machine = syn::parse2(quote! {
+            Context = FSM;
+
+            Const;
+
+            States {
+                S1 = S1,
+                S2 = S2
+            }
+
+            Final { S2 }
+
+            Events {
+                EVENT1 = Event1
+            }
+
+            Transitions {
+                EVENT1 [ S1 => S2 ]
+            }
+        })
+        .unwrap();
+
+        let tokens = machine.to_token_stream().to_string();
+        assert!(tokens.contains(&quote!(pub const fn new() -> Machine).to_string()));
+        assert!(tokens.contains(
+            &quote! {
+                context: <FSM as ::fsm_rs::ConstDefault>::DEFAULT,
+                current_state: State::S1(<S1 as ::fsm_rs::ConstDefault>::DEFAULT),
+            }
+            .to_string()
+        ));
+    }
+
+    #[test]
+    fn test_machine_undeclared_transition_state() {
+        let error = syn::parse2::<Machine>(quote! {
+            Context = FSM;
+
+            States {
+                S1 = S1,
+                S2 = S2
+            }
+
+            Events {
+                EVENT1 = Event1
+            }
+
+            Transitions {
+                EVENT1 [ S1 => S2, S2 => S1, S2 => S3 ]
+            }
+        })
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), "state `S3` is not declared in States");
+    }
+}
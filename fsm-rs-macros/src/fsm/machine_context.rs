@@ -1,10 +1,6 @@
-use proc_macro2::{Span, TokenStream};
-use quote::{quote, ToTokens};
-use syn::token::{Colon, Pub};
 use syn::{
-    parenthesized,
     parse::{Parse, ParseStream, Result},
-    parse_quote, Expr, Field, Fields, Ident, ItemStruct, Token, Type, VisPublic, Visibility,
+    Ident, Token, Type,
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -19,20 +15,27 @@ impl Parse for MachineContext {
     /// Context = Machine;
     /// ```
     fn parse(input: ParseStream<'_>) -> Result<Self> {
-        /// Context = Machine;
-        /// _______
+        // Context = Machine;
+        // _______
         let context_magic: Ident = Ident::parse(input)?;
 
-        /// Context = Machine;
-        ///         _
+        if context_magic != "Context" {
+            return Err(syn::Error::new(
+                context_magic.span(),
+                "expected Context = ...;",
+            ));
+        }
+
+        // Context = Machine;
+        //         _
         let _: Token![=] = input.parse()?;
 
-        /// Context = Machine;
-        ///           _______
+        // Context = Machine;
+        //           _______
         let context_type: Type = Type::parse(input)?;
 
-        /// Context = Machine;
-        ///                  _
+        // Context = Machine;
+        //                  _
         let _: Token![;] = input.parse()?;
 
         Ok(MachineContext { context_type })
@@ -52,12 +55,15 @@ mod tests {
     use syn::{parse2, parse_quote};
 
     #[test]
-    fn test_initial_state_parse() {
-        let _: MachineContext = parse2(quote! {
-            pub struct FSM {
-                str: String
-            }
+    fn test_machine_context_parse() {
+        let context: MachineContext = parse2(quote! {
+            Context = FSM;
         })
         .unwrap();
+
+        let expected: Type = parse_quote!(FSM);
+        assert_eq!(context.context_type(), expected);
+
+        assert!(parse2::<MachineContext>(quote!(Ctx = FSM;)).is_err());
     }
 }
@@ -0,0 +1,317 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    braced, parenthesized,
+    parse::{Parse, ParseStream, Result},
+    parse_quote, Expr, FnArg, Ident, Pat, ReturnType, Signature, Token, Type,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum MethodKind {
+    /// `get field: Type`
+    Get { field: Ident, field_type: Type },
+    /// `set field: Type`
+    Set { field: Ident, field_type: Type },
+    /// `fn name(&self, ..) -> Type`, optionally preceded by `default(expr)`
+    Fn {
+        default: Option<Expr>,
+        signature: Box<Signature>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Method {
+    pub states: Vec<Ident>,
+    pub kind: MethodKind,
+}
+
+impl Parse for Method {
+    /// example methods:
+    ///
+    /// ```text
+    /// S1 => get count: u8
+    /// S1 => set count: u8
+    /// S1, S2 => fn can_pass(&self) -> bool
+    /// S1, S2 => default(false) fn working(&self) -> bool
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // S1, S2 => ...
+        // ^^^^^^
+        let mut states = vec![input.parse::<Ident>()?];
+        while !input.peek(Token![=>]) {
+            let _: Token![,] = input.parse()?;
+            states.push(input.parse()?);
+        }
+
+        // S1, S2 => ...
+        //        ^^
+        let _: Token![=>] = input.parse()?;
+
+        let kind = if input.peek(Token![fn]) {
+            MethodKind::Fn {
+                default: None,
+                signature: Box::new(input.parse()?),
+            }
+        } else {
+            let magic: Ident = input.parse()?;
+
+            if magic == "get" || magic == "set" {
+                // get count: u8
+                //     ^^^^^^^^^
+                let field: Ident = input.parse()?;
+                let _: Token![:] = input.parse()?;
+                let field_type: Type = input.parse()?;
+
+                if magic == "get" {
+                    MethodKind::Get { field, field_type }
+                } else {
+                    MethodKind::Set { field, field_type }
+                }
+            } else if magic == "default" {
+                // default(false) fn working(&self) -> bool
+                //        ^^^^^^^ ^^^^^^^^^^^^^^^^^^^^^^^^^
+                let content;
+                parenthesized!(content in input);
+                MethodKind::Fn {
+                    default: Some(content.parse()?),
+                    signature: Box::new(input.parse()?),
+                }
+            } else {
+                return Err(syn::Error::new(
+                    magic.span(),
+                    "expected get, set, fn or default(..) fn",
+                ));
+            }
+        };
+
+        if let MethodKind::Fn { signature, .. } = &kind {
+            if signature.receiver().is_none() {
+                return Err(syn::Error::new(
+                    signature.ident.span(),
+                    "delegated method must take self",
+                ));
+            }
+        }
+
+        Ok(Method { states, kind })
+    }
+}
+
+impl Method {
+    fn to_tokens_for(&self, enum_name: &Ident) -> TokenStream {
+        let states = &self.states;
+
+        match &self.kind {
+            MethodKind::Get { field, field_type } => quote! {
+                pub fn #field(&self) -> Option<&#field_type> {
+                    #[allow(unreachable_patterns)]
+                    match self {
+                        #( #enum_name::#states(state) => Some(&state.#field), )*
+                        _ => None,
+                    }
+                }
+            },
+            MethodKind::Set { field, field_type } => {
+                let field_mut = format_ident!("{}_mut", field);
+                quote! {
+                    pub fn #field_mut(&mut self) -> Option<&mut #field_type> {
+                        #[allow(unreachable_patterns)]
+                        match self {
+                            #( #enum_name::#states(state) => Some(&mut state.#field), )*
+                            _ => None,
+                        }
+                    }
+                }
+            }
+            MethodKind::Fn { default, signature } => {
+                let name = &signature.ident;
+                let args: Vec<_> = signature
+                    .inputs
+                    .iter()
+                    .filter_map(|v| match v {
+                        FnArg::Typed(arg) => match &*arg.pat {
+                            Pat::Ident(pat) => Some(&pat.ident),
+                            _ => None,
+                        },
+                        FnArg::Receiver(_) => None,
+                    })
+                    .collect();
+                let call = quote!(state.#name(#( #args ),*));
+
+                match default {
+                    Some(default) => quote! {
+                        pub #signature {
+                            #[allow(unreachable_patterns)]
+                            match self {
+                                #( #enum_name::#states(state) => #call, )*
+                                _ => #default,
+                            }
+                        }
+                    },
+                    None => {
+                        // without a default the other states answer `None`
+                        let mut signature = signature.clone();
+                        let output: Type = match &signature.output {
+                            ReturnType::Default => parse_quote!(()),
+                            ReturnType::Type(_, output) => (**output).clone(),
+                        };
+                        signature.output = parse_quote!(-> Option<#output>);
+
+                        quote! {
+                            pub #signature {
+                                #[allow(unreachable_patterns)]
+                                match self {
+                                    #( #enum_name::#states(state) => Some(#call), )*
+                                    _ => None,
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Methods(pub Vec<Method>);
+
+impl Parse for Methods {
+    /// example methods section:
+    ///
+    /// ```text
+    /// Methods {
+    ///     S1 => get count: u8,
+    ///     S1 => set count: u8,
+    ///     S1, S2 => default(false) fn working(&self) -> bool
+    /// }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // Methods { ... }
+        // -------
+        let magic = Ident::parse(input)?;
+
+        if magic != "Methods" {
+            return Err(syn::Error::new(magic.span(), "expected Methods { ... }"));
+        }
+
+        let content;
+        braced!(content in input);
+
+        Methods::parse_list(&content)
+    }
+}
+
+impl Methods {
+    /// Parse comma separated methods up to the end of `input`.
+    pub fn parse_list(input: ParseStream<'_>) -> Result<Self> {
+        let mut methods = Vec::new();
+
+        while !input.is_empty() {
+            methods.push(input.parse()?);
+
+            if input.is_empty() {
+                break;
+            }
+            let _: Token![,] = input.parse()?;
+        }
+
+        Ok(Methods(methods))
+    }
+
+    /// Generate an `impl` block adding the methods to `enum_name`, whose
+    /// variants wrap the per-state types.
+    pub fn to_impl_tokens(&self, enum_name: &Ident) -> TokenStream {
+        let methods: Vec<_> = self.0.iter().map(|v| v.to_tokens_for(enum_name)).collect();
+
+        quote! {
+            impl #enum_name {
+                #( #methods )*
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+    use syn::parse::Parser;
+
+    #[test]
+    fn test_methods_parse_and_to_tokens() {
+        let methods = Methods::parse_list
+            .parse2(quote! {
+                S1 => get count: u8,
+                S1, S2 => default(false) fn working(&self) -> bool,
+            })
+            .unwrap();
+
+        assert_eq!(methods.0.len(), 2);
+        assert_eq!(methods.0[1].states.len(), 2);
+
+        let left = quote! {
+            impl State {
+                pub fn count(&self) -> Option<&u8> {
+                    #[allow(unreachable_patterns)]
+                    match self {
+                        State::S1(state) => Some(&state.count),
+                        _ => None,
+                    }
+                }
+                pub fn working(&self) -> bool {
+                    #[allow(unreachable_patterns)]
+                    match self {
+                        State::S1(state) => state.working(),
+                        State::S2(state) => state.working(),
+                        _ => false,
+                    }
+                }
+            }
+        };
+
+        let right = methods.to_impl_tokens(&format_ident!("State"));
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+
+        assert!(Methods::parse_list
+            .parse2(quote!(S1 => fn free() -> bool))
+            .is_err());
+    }
+
+    #[test]
+    fn test_methods_section_parse() {
+        let methods: Methods = syn::parse2(quote! {
+            Methods {
+                S1 => set count: u8,
+                S2 => fn can_pass(&self, car: u8) -> bool
+            }
+        })
+        .unwrap();
+
+        let left = quote! {
+            impl State {
+                pub fn count_mut(&mut self) -> Option<&mut u8> {
+                    #[allow(unreachable_patterns)]
+                    match self {
+                        State::S1(state) => Some(&mut state.count),
+                        _ => None,
+                    }
+                }
+                pub fn can_pass(&self, car: u8) -> Option<bool> {
+                    #[allow(unreachable_patterns)]
+                    match self {
+                        State::S2(state) => Some(state.can_pass(car)),
+                        _ => None,
+                    }
+                }
+            }
+        };
+
+        let right = methods.to_impl_tokens(&format_ident!("State"));
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+
+        assert!(syn::parse2::<Methods>(quote!(Getters {})).is_err());
+    }
+}
@@ -0,0 +1,11 @@
+pub mod analysis;
+pub mod events;
+pub mod history;
+pub mod machine;
+pub mod machine_context;
+pub mod methods;
+pub mod settings;
+pub mod states;
+pub mod table;
+pub mod transitions;
+pub mod typestate;
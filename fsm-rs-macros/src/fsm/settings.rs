@@ -0,0 +1,307 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream, Result},
+    token, Ident, Token, Type,
+};
+
+use crate::fsm::history::History;
+
+/// Shape of the generated `Machine`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Mode {
+    /// a single `Machine` dispatching `Event`s at runtime
+    #[default]
+    Match,
+    /// a `Machine<S>` per state type with one consuming method per transition,
+    /// without calling guards or `entry()`/`exit()` hooks
+    Typestate,
+}
+
+/// How the `Match` mode `Machine::event` finds the transition to take.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Backend {
+    /// nested `match` on the event and the current state
+    #[default]
+    Match,
+    /// lookup in a static `[[Option<TransitionIdx>; EVENTS]; STATES]` table
+    Table,
+}
+
+/// Who picks the target of an external transition with a single target in
+/// `Mode = Match`. Several targets are always picked by the handler, which
+/// returns the generated `<From><Event>Target` enum.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Dispatch {
+    /// `exit()` of the state being left returns the next `State`
+    #[default]
+    Exit,
+    /// `on_<event>(&self, &mut Context, Event)` of the state being left
+    /// returns the target, once `exit()` did not refuse the transition
+    Handler,
+}
+
+/// Optional `Name = value;` / `Name;` settings following `Context = ...;`.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Settings {
+    pub history: Option<History>,
+    /// type `Machine::into_output()` converts the final state into
+    pub output: Option<Type>,
+    /// reject ambiguous or duplicated transitions instead of merging them
+    pub deterministic: bool,
+    /// restore the state and context when `Machine::event` fails
+    pub transactional: bool,
+    /// state entered when `entry()` fails, unless the failing state has its
+    /// own `S on_error => ...` route
+    pub error_state: Option<Ident>,
+    pub mode: Mode,
+    pub backend: Backend,
+    pub dispatch: Dispatch,
+    /// make `Machine::new` a `const fn`, building the context and the
+    /// initial state from `ConstDefault::DEFAULT`
+    pub constant: bool,
+}
+
+impl Settings {
+    /// The `const` keyword, if any, of the generated constructors and getters,
+    /// and the expressions building the context and the initial state. With
+    /// `Const;` both come from `ConstDefault::DEFAULT`.
+    pub fn constructor_tokens(
+        &self,
+        context_type: &Type,
+        initial_type: &Type,
+    ) -> (TokenStream, TokenStream, TokenStream) {
+        if self.constant {
+            (
+                quote!(const),
+                quote!(<#context_type as ::fsm_rs::ConstDefault>::DEFAULT),
+                quote!(<#initial_type as ::fsm_rs::ConstDefault>::DEFAULT),
+            )
+        } else {
+            (
+                TokenStream::new(),
+                quote!(#context_type::default()),
+                quote!(Default::default()),
+            )
+        }
+    }
+}
+
+impl Parse for Settings {
+    /// example settings:
+    ///
+    /// ```text
+    /// History = 32;
+    /// Output = Outcome;
+    /// Deterministic;
+    /// Transactional;
+    /// ErrorState = Failed;
+    /// Mode = Typestate;
+    /// Backend = Table;
+    /// Dispatch = Handler;
+    /// Const;
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut settings = Settings::default();
+        let mut mode_set = false;
+        let mut backend_set = false;
+        let mut dispatch_set = false;
+
+        // stop at the first section, e.g. `States { ... }`
+        while input.peek(Ident) && !input.peek2(token::Brace) {
+            let name: Ident = input.fork().parse()?;
+
+            if name == "History" {
+                if settings.history.is_some() {
+                    return Err(syn::Error::new(name.span(), "duplicate History setting"));
+                }
+                settings.history = Some(History::parse(input)?);
+            } else if name == "Output" {
+                if settings.output.is_some() {
+                    return Err(syn::Error::new(name.span(), "duplicate Output setting"));
+                }
+
+                // Output = Outcome;
+                let _: Ident = input.parse()?;
+                let _: Token![=] = input.parse()?;
+                settings.output = Some(input.parse()?);
+                let _: Token![;] = input.parse()?;
+            } else if name == "Deterministic" {
+                if settings.deterministic {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "duplicate Deterministic setting",
+                    ));
+                }
+
+                // Deterministic;
+                let _: Ident = input.parse()?;
+                let _: Token![;] = input.parse()?;
+                settings.deterministic = true;
+            } else if name == "Transactional" {
+                if settings.transactional {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "duplicate Transactional setting",
+                    ));
+                }
+
+                // Transactional;
+                let _: Ident = input.parse()?;
+                let _: Token![;] = input.parse()?;
+                settings.transactional = true;
+            } else if name == "ErrorState" {
+                if settings.error_state.is_some() {
+                    return Err(syn::Error::new(name.span(), "duplicate ErrorState setting"));
+                }
+
+                // ErrorState = Failed;
+                let _: Ident = input.parse()?;
+                let _: Token![=] = input.parse()?;
+                settings.error_state = Some(input.parse()?);
+                let _: Token![;] = input.parse()?;
+            } else if name == "Const" {
+                if settings.constant {
+                    return Err(syn::Error::new(name.span(), "duplicate Const setting"));
+                }
+
+                // Const;
+                let _: Ident = input.parse()?;
+                let _: Token![;] = input.parse()?;
+                settings.constant = true;
+            } else if name == "Mode" {
+                if mode_set {
+                    return Err(syn::Error::new(name.span(), "duplicate Mode setting"));
+                }
+
+                // Mode = Typestate;
+                let _: Ident = input.parse()?;
+                let _: Token![=] = input.parse()?;
+                let mode: Ident = input.parse()?;
+                settings.mode = if mode == "Match" {
+                    Mode::Match
+                } else if mode == "Typestate" {
+                    Mode::Typestate
+                } else {
+                    return Err(syn::Error::new(
+                        mode.span(),
+                        "expected Mode = Match; or Mode = Typestate;",
+                    ));
+                };
+                let _: Token![;] = input.parse()?;
+                mode_set = true;
+            } else if name == "Backend" {
+                if backend_set {
+                    return Err(syn::Error::new(name.span(), "duplicate Backend setting"));
+                }
+
+                // Backend = Table;
+                let _: Ident = input.parse()?;
+                let _: Token![=] = input.parse()?;
+                let backend: Ident = input.parse()?;
+                settings.backend = if backend == "Match" {
+                    Backend::Match
+                } else if backend == "Table" {
+                    Backend::Table
+                } else {
+                    return Err(syn::Error::new(
+                        backend.span(),
+                        "expected Backend = Match; or Backend = Table;",
+                    ));
+                };
+                let _: Token![;] = input.parse()?;
+                backend_set = true;
+            } else if name == "Dispatch" {
+                if dispatch_set {
+                    return Err(syn::Error::new(name.span(), "duplicate Dispatch setting"));
+                }
+
+                // Dispatch = Handler;
+                let _: Ident = input.parse()?;
+                let _: Token![=] = input.parse()?;
+                let dispatch: Ident = input.parse()?;
+                settings.dispatch = if dispatch == "Exit" {
+                    Dispatch::Exit
+                } else if dispatch == "Handler" {
+                    Dispatch::Handler
+                } else {
+                    return Err(syn::Error::new(
+                        dispatch.span(),
+                        "expected Dispatch = Exit; or Dispatch = Handler;",
+                    ));
+                };
+                let _: Token![;] = input.parse()?;
+                dispatch_set = true;
+            } else {
+                return Err(syn::Error::new(name.span(), "unknown setting"));
+            }
+        }
+
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn test_settings_parse() {
+        let settings: Settings = syn::parse2(quote! {}).unwrap();
+        assert_eq!(settings, Settings::default());
+
+        let settings: Settings = syn::parse2(quote! {
+            History = 8;
+        })
+        .unwrap();
+        assert!(settings.history.is_some());
+        assert!(settings.output.is_none());
+
+        let settings: Settings = syn::parse2(quote! {
+            History = 8;
+            Output = Result<u8, ()>;
+            Deterministic;
+            Transactional;
+            ErrorState = Failed;
+            Const;
+        })
+        .unwrap();
+        assert!(settings.output.is_some());
+        assert!(settings.deterministic);
+        assert!(settings.transactional);
+        assert_eq!(settings.error_state.unwrap(), "Failed");
+        assert!(settings.constant);
+        assert_eq!(settings.mode, Mode::Match);
+
+        let settings: Settings = syn::parse2(quote! {
+            Mode = Typestate;
+        })
+        .unwrap();
+        assert_eq!(settings.mode, Mode::Typestate);
+        assert_eq!(settings.backend, Backend::Match);
+
+        let settings: Settings = syn::parse2(quote! {
+            Backend = Table;
+        })
+        .unwrap();
+        assert_eq!(settings.backend, Backend::Table);
+        assert_eq!(settings.dispatch, Dispatch::Exit);
+
+        let settings: Settings = syn::parse2(quote! {
+            Dispatch = Handler;
+        })
+        .unwrap();
+        assert_eq!(settings.dispatch, Dispatch::Handler);
+
+        assert!(syn::parse2::<Settings>(quote! {
+            Mode = Table;
+        })
+        .is_err());
+
+        assert!(syn::parse2::<Settings>(quote! {
+            Unknown = 8;
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream, Result},
+    punctuated::Punctuated,
+    Ident, Token, Type,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct State {
+    pub state_name: Ident,
+    pub state_type: Type,
+}
+
+impl Parse for State {
+    /// example state:
+    ///
+    /// ```text
+    /// S1 = S1
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // S1 = S1
+        // __
+        let state_name: Ident = Ident::parse(input)?;
+
+        // S1 = S1
+        //    _
+        let _: Token![=] = input.parse()?;
+
+        // S1 = S1
+        //      __
+        let state_type: Type = Type::parse(input)?;
+
+        Ok(State {
+            state_name,
+            state_type,
+        })
+    }
+}
+
+impl ToTokens for State {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let state_name = &self.state_name;
+        let state_type = &self.state_type;
+        tokens.extend(quote!(
+            #state_name(#state_type)
+        ));
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct States(pub Vec<State>);
+
+impl Parse for States {
+    /// example states:
+    ///
+    /// ```text
+    /// States {
+    ///     S1 = S1,
+    ///     S2 = S2,
+    ///     S3 = S3,
+    ///     S4 = S4,
+    ///     S5 = S5
+    /// }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // States { ... }
+        // -----------
+        let states_magic = Ident::parse(input)?;
+
+        if states_magic != "States" {
+            return Err(input.error("expected States { ... }"));
+        }
+
+        let content;
+        braced!(content in input);
+
+        let states: Punctuated<State, Token![,]> = content.parse_terminated(State::parse)?;
+
+        if states.is_empty() {
+            return Err(syn::Error::new(
+                states_magic.span(),
+                "States must declare at least the initial state",
+            ));
+        }
+
+        Ok(States(states.into_iter().collect()))
+    }
+}
+
+impl States {
+    /// The machine starts in the first declared state.
+    pub fn initial(&self) -> &State {
+        &self.0[0]
+    }
+
+    pub fn get(&self, name: &Ident) -> Option<&State> {
+        self.0.iter().find(|v| v.state_name == *name)
+    }
+}
+
+/// States in which the machine terminates and accepts no further events.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct FinalStates(pub Vec<Ident>);
+
+impl Parse for FinalStates {
+    /// example final states:
+    ///
+    /// ```text
+    /// Final { S4, S5 }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // Final { ... }
+        // -----
+        let magic = Ident::parse(input)?;
+
+        if magic != "Final" {
+            return Err(syn::Error::new(magic.span(), "expected Final { ... }"));
+        }
+
+        let content;
+        braced!(content in input);
+
+        let states: Punctuated<Ident, Token![,]> = content.parse_terminated(Ident::parse)?;
+        Ok(FinalStates(states.into_iter().collect()))
+    }
+}
+
+impl FinalStates {
+    pub fn contains(&self, name: &Ident) -> bool {
+        self.0.contains(name)
+    }
+}
+
+impl ToTokens for States {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let states = &self.0;
+        let names: Vec<_> = states.iter().map(|v| &v.state_name).collect();
+        let name_strs: Vec<_> = names.iter().map(|v| v.to_string()).collect();
+        let count = names.len();
+        tokens.extend(quote!(
+            #[derive(Clone, Debug, PartialEq)]
+            pub enum State {
+                #(#states),*
+            }
+
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+            pub enum StateId {
+                #(#names),*
+            }
+
+            impl State {
+                pub fn id(&self) -> StateId {
+                    match self {
+                        #(State::#names(_) => StateId::#names),*
+                    }
+                }
+            }
+
+            impl StateId {
+                pub const ALL: [StateId; #count] = [#(StateId::#names),*];
+
+                pub fn name(self) -> &'static str {
+                    match self {
+                        #(StateId::#names => #name_strs),*
+                    }
+                }
+            }
+
+            impl ::fsm_rs::Id for StateId {
+                fn all() -> &'static [StateId] {
+                    &StateId::ALL
+                }
+
+                fn name(self) -> &'static str {
+                    StateId::name(self)
+                }
+            }
+
+            impl ::core::fmt::Display for StateId {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str(self.name())
+                }
+            }
+
+            /// Returned when parsing a string that names no state.
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub struct ParseStateIdError;
+
+            impl ::core::fmt::Display for ParseStateIdError {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str("unknown state")
+                }
+            }
+
+            impl ::core::str::FromStr for StateId {
+                type Err = ParseStateIdError;
+
+                fn from_str(s: &str) -> Result<StateId, ParseStateIdError> {
+                    match s {
+                        #(#name_strs => Ok(StateId::#names),)*
+                        _ => Err(ParseStateIdError),
+                    }
+                }
+            }
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::TokenStream;
+    use syn;
+
+    #[test]
+    fn test_states_parse_and_to_tokens() {
+        let states: States = syn::parse2(quote! {
+            States {
+                S1 = S1,
+                S2 = S2
+            }
+        })
+        .unwrap();
+
+        let left = quote! {
+            #[derive(Clone, Debug, PartialEq)]
+            pub enum State {
+                S1(S1),
+                S2(S2)
+            }
+
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+            pub enum StateId {
+                S1,
+                S2
+            }
+
+            impl State {
+                pub fn id(&self) -> StateId {
+                    match self {
+                        State::S1(_) => StateId::S1,
+                        State::S2(_) => StateId::S2
+                    }
+                }
+            }
+
+            impl StateId {
+                pub const ALL: [StateId; 2usize] = [StateId::S1, StateId::S2];
+
+                pub fn name(self) -> &'static str {
+                    match self {
+                        StateId::S1 => "S1",
+                        StateId::S2 => "S2"
+                    }
+                }
+            }
+
+            impl ::fsm_rs::Id for StateId {
+                fn all() -> &'static [StateId] {
+                    &StateId::ALL
+                }
+
+                fn name(self) -> &'static str {
+                    StateId::name(self)
+                }
+            }
+
+            impl ::core::fmt::Display for StateId {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str(self.name())
+                }
+            }
+
+            /// Returned when parsing a string that names no state.
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            pub struct ParseStateIdError;
+
+            impl ::core::fmt::Display for ParseStateIdError {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str("unknown state")
+                }
+            }
+
+            impl ::core::str::FromStr for StateId {
+                type Err = ParseStateIdError;
+
+                fn from_str(s: &str) -> Result<StateId, ParseStateIdError> {
+                    match s {
+                        "S1" => Ok(StateId::S1),
+                        "S2" => Ok(StateId::S2),
+                        _ => Err(ParseStateIdError),
+                    }
+                }
+            }
+        };
+
+        let mut right = TokenStream::new();
+        states.to_tokens(&mut right);
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+}
@@ -0,0 +1,395 @@
+use heck::SnakeCase;
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+use std::collections::BTreeSet;
+use syn::Ident;
+
+use crate::fsm::{
+    events::Events,
+    settings::Settings,
+    states::States,
+    transitions::{
+        event_fn_signature, target_enum_name, trace_event, trace_record_expr, trace_span,
+        Transitions, UNDECLARED_TARGET,
+    },
+};
+
+/// One declared (state, event) pair, addressed by its index in the table.
+#[derive(Debug, PartialEq)]
+struct Entry<'a> {
+    from: &'a Ident,
+    event_name: &'a Ident,
+    internal: bool,
+    targets: &'a BTreeSet<Ident>,
+}
+
+impl Transitions {
+    /// The transitions in state-major order and the `[state][event]` lookup
+    /// table of indices into them, both following declaration order.
+    fn table<'a>(
+        &'a self,
+        states: &States,
+        events: &Events,
+    ) -> (Vec<Entry<'a>>, Vec<Vec<Option<usize>>>) {
+        let mut entries = Vec::new();
+        let mut rows = Vec::new();
+
+        for state in &states.0 {
+            let mut row = Vec::new();
+
+            for event in &events.0 {
+                let transition = self.0.iter().find(|v| v.event_name == event.event_name);
+                let entry = transition.and_then(|v| {
+                    let (from, targets) = v.pairs.get_key_value(&state.state_name)?;
+                    Some(Entry {
+                        from,
+                        event_name: &v.event_name,
+                        internal: v.internal.contains(from),
+                        targets,
+                    })
+                });
+
+                row.push(entry.map(|v| {
+                    entries.push(v);
+                    entries.len() - 1
+                }));
+            }
+
+            rows.push(row);
+        }
+
+        (entries, rows)
+    }
+
+    /// Generate `Machine::event` for `Backend = Table;`. Instead of a match
+    /// arm per (event, state) pair it looks the transition up in a static
+    /// table, and only matches per state or per event where a callback of
+    /// the concrete type has to be called.
+    pub fn to_table_event_fn_tokens(
+        &self,
+        machine_name: &str,
+        settings: &Settings,
+        states: &States,
+        events: &Events,
+    ) -> TokenStream {
+        let (entries, rows) = self.table(states, events);
+
+        let state_count = states.0.len();
+        let event_count = events.0.len();
+        let entry_count = entries.len();
+        let index_type = if entry_count <= usize::from(u16::MAX) {
+            quote!(u16)
+        } else {
+            quote!(u32)
+        };
+
+        let rows = rows.iter().map(|row| {
+            let cells = row.iter().map(|v| match v {
+                Some(index) => {
+                    let index = Literal::usize_unsuffixed(*index);
+                    quote!(Some(#index))
+                }
+                None => quote!(None),
+            });
+            quote!([#( #cells ),*])
+        });
+
+        let table_entries = entries.iter().map(|v| {
+            let internal = v.internal;
+            let targets = v.targets.iter();
+            quote! {
+                TransitionEntry {
+                    internal: #internal,
+                    targets: &[#( StateId::#targets ),*],
+                }
+            }
+        });
+
+        // events with at least one transition run their guard
+        let guarded: Vec<_> = self
+            .0
+            .iter()
+            .filter(|v| !v.pairs.is_empty())
+            .map(|v| &v.event_name)
+            .collect();
+
+        let exiting: BTreeSet<_> = entries
+            .iter()
+            .filter(|v| !v.internal)
+            .map(|v| v.from)
+            .collect();
+
+        // exit() picks single targets, handlers returning the restricted
+        // target enum pick among several
+        let exit_routed: BTreeSet<_> = entries
+            .iter()
+            .filter(|v| !v.internal && v.targets.len() == 1)
+            .map(|v| v.from)
+            .collect();
+        let handlers: Vec<_> = entries
+            .iter()
+            .filter(|v| !v.internal && v.targets.len() > 1)
+            .map(|v| {
+                let from = v.from;
+                let event_name = v.event_name;
+                let handler = format_ident!("on_{}", event_name.to_string().to_snake_case());
+                let target = target_enum_name(from, event_name);
+                let tos = v.targets.iter();
+                // exit() may refuse before the handler touches the context
+                quote! {
+                    (State::#from(state), Event::#event_name(data)) => match state.exit() {
+                        Ok(_) => Ok(match state.#handler(&mut self.context, data.clone()) {
+                            #( #target::#tos(next) => State::#tos(next), )*
+                        }),
+                        Err(err) => Err(err),
+                    },
+                }
+            })
+            .collect();
+
+        let pick_by_exit = quote! {
+            match &mut self.current_state {
+                #( State::#exit_routed(state) => state.exit(), )*
+                _ => unreachable!(),
+            }
+        };
+        let pick_by_handler = quote! {
+            match (&mut self.current_state, &event) {
+                #( #handlers )*
+                _ => unreachable!(),
+            }
+        };
+        let pick = if handlers.is_empty() {
+            pick_by_exit
+        } else if exit_routed.is_empty() {
+            pick_by_handler
+        } else {
+            quote! {
+                if transition.targets.len() > 1 {
+                    #pick_by_handler
+                } else {
+                    #pick_by_exit
+                }
+            }
+        };
+
+        let actions = entries.iter().filter(|v| v.internal).map(|v| {
+            let from = v.from;
+            let event_name = v.event_name;
+            let action = format_ident!("on_{}", event_name.to_string().to_snake_case());
+            quote! {
+                (State::#from(state), Event::#event_name(data)) => state.#action(data),
+            }
+        });
+
+        let (record_internal, record_transition) = if settings.history.is_some() {
+            (
+                quote!(self.history.push(from, event.id(), from);),
+                quote!(self.history.push(from, event.id(), next.id());),
+            )
+        } else {
+            Default::default()
+        };
+
+        let trace_span = trace_span(machine_name);
+        let trace_event_name = trace_record_expr("event", quote!(event.id().name()));
+        let trace_from = trace_record_expr("from", quote!(from.name()));
+        let trace_to_internal = trace_record_expr("to", quote!(from.name()));
+        let trace_to = trace_record_expr("to", quote!(next.id().name()));
+        let trace_terminated = trace_event("warn", "machine is terminated", None);
+        let trace_guard_failed = trace_event("warn", "event guard failed", Some(quote!(err)));
+        let trace_unhandled = trace_event("debug", "event not handled", None);
+        let trace_exit_failed = trace_event("warn", "state exit failed", Some(quote!(err)));
+        let trace_undeclared_target = trace_event("warn", UNDECLARED_TARGET, None);
+        let trace_internal = trace_event("debug", "internal transition", None);
+        let trace_transition = trace_event("debug", "transition", None);
+
+        // a match without arms diverges, so each half is only generated when
+        // the machine has transitions of that kind
+        let internal_branch = if entries.iter().any(|v| v.internal) {
+            quote! {
+                if transition.internal {
+                    #[allow(unreachable_patterns)]
+                    match (&mut self.current_state, &event) {
+                        #( #actions )*
+                        _ => unreachable!(),
+                    }
+                    #trace_to_internal
+                    #trace_internal
+                    #record_internal
+                    self.observer.on_transition(&self.current_state, &self.current_state, &event);
+                    return Ok(true);
+                }
+            }
+        } else {
+            TokenStream::new()
+        };
+
+        let external_branch = if exiting.is_empty() {
+            quote!(unreachable!())
+        } else {
+            quote! {
+                #[allow(unreachable_patterns)]
+                let exited = #pick;
+                let next = match exited {
+                    Ok(next) => next,
+                    Err(err) => {
+                        #trace_exit_failed
+                        let err = ::fsm_rs::TransitionError::Rejected(err);
+                        self.observer.on_rejected(&self.current_state, &event, &err);
+                        return Err(err);
+                    }
+                };
+
+                if !transition.targets.contains(&next.id()) {
+                    #trace_undeclared_target
+                    let err = ::fsm_rs::TransitionError::Rejected(#UNDECLARED_TARGET);
+                    self.observer.on_rejected(&self.current_state, &event, &err);
+                    return Err(err);
+                }
+
+                #trace_to
+                #trace_transition
+                #record_transition
+                self.observer.on_transition(&self.current_state, &next, &event);
+                self.current_state = next;
+                self.enter(&event)
+            }
+        };
+
+        let signature = event_fn_signature(settings);
+
+        quote! {
+            #signature {
+                /// Index into `TRANSITIONS`.
+                type TransitionIdx = #index_type;
+
+                struct TransitionEntry {
+                    internal: bool,
+                    targets: &'static [StateId],
+                }
+
+                static TRANSITIONS: [TransitionEntry; #entry_count] = [#( #table_entries ),*];
+
+                static TRANSITION_TABLE: [[Option<TransitionIdx>; #event_count]; #state_count] = [#( #rows ),*];
+
+                #trace_span
+                self.observer.on_event_received(&self.current_state, &event);
+
+                if self.is_terminated() {
+                    #trace_terminated
+                    let err = ::fsm_rs::TransitionError::Terminated;
+                    self.observer.on_rejected(&self.current_state, &event, &err);
+                    return Err(err);
+                }
+
+                #[allow(unreachable_patterns)]
+                let guard = match &event {
+                    #( Event::#guarded(data) => Some(data.on()), )*
+                    _ => None,
+                };
+                match guard {
+                    Some(Ok(())) => {
+                        #trace_event_name
+                    }
+                    Some(Err(err)) => {
+                        #trace_event_name
+                        #trace_guard_failed
+                        let err = ::fsm_rs::TransitionError::Rejected(err);
+                        self.observer.on_rejected(&self.current_state, &event, &err);
+                        return Err(err);
+                    }
+                    None => {
+                        #trace_unhandled
+                        self.observer.on_unhandled(&self.current_state, &event);
+                        return Ok(false);
+                    }
+                }
+
+                let from = self.current_state.id();
+                let transition = match TRANSITION_TABLE[from as usize][event.id() as usize] {
+                    Some(index) => &TRANSITIONS[index as usize],
+                    None => {
+                        #trace_unhandled
+                        self.observer.on_unhandled(&self.current_state, &event);
+                        return Ok(false);
+                    }
+                };
+                #trace_from
+
+                #internal_branch
+
+                #external_branch
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    #[test]
+    fn test_transitions_table() {
+        let states: States = syn::parse2(quote! {
+            States {
+                S1 = S1,
+                S2 = S2,
+                S3 = S3
+            }
+        })
+        .unwrap();
+        let events: Events = syn::parse2(quote! {
+            Events {
+                E1 = E1,
+                E2 = E2
+            }
+        })
+        .unwrap();
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E2 [
+                    S1 ~> S1,
+                    S3 => S1,
+                ],
+                E1 [
+                    S1 => S2,
+                    S1 => S3,
+                ]
+            }
+        })
+        .unwrap();
+
+        let (entries, rows) = transitions.table(&states, &events);
+
+        assert_eq!(
+            rows,
+            vec![
+                vec![Some(0), Some(1)],
+                vec![None, None],
+                vec![None, Some(2)]
+            ]
+        );
+
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|v| {
+                (
+                    v.from.to_string(),
+                    v.event_name.to_string(),
+                    v.internal,
+                    v.targets.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("S1".to_string(), "E1".to_string(), false, 2),
+                ("S1".to_string(), "E2".to_string(), true, 1),
+                ("S3".to_string(), "E2".to_string(), false, 1),
+            ]
+        );
+    }
+}
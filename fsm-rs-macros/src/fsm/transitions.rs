@@ -0,0 +1,1477 @@
+use heck::{CamelCase, SnakeCase};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use std::collections::{BTreeMap, BTreeSet};
+use syn::{
+    braced, bracketed, parenthesized,
+    parse::{Parse, ParseStream, Result},
+    punctuated::Punctuated,
+    token, Ident, Token,
+};
+
+use crate::fsm::settings::{Dispatch, Settings};
+use crate::fsm::states::{FinalStates, States};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct TransitionPair {
+    /// source states, empty for the `*` wildcard
+    pub from: Vec<Ident>,
+    pub to: Ident,
+    pub internal: bool,
+}
+
+impl TransitionPair {
+    fn new(from: Vec<Ident>, to: Ident, internal: bool) -> Result<Self> {
+        if internal && (from.len() != 1 || from[0] != to) {
+            return Err(syn::Error::new(
+                to.span(),
+                "internal transition must target its single source state",
+            ));
+        }
+
+        Ok(TransitionPair { from, to, internal })
+    }
+
+    /// `S1 | S2` or `*`
+    fn parse_sources(input: ParseStream<'_>) -> Result<Vec<Ident>> {
+        if input.peek(Token![*]) {
+            let _: Token![*] = input.parse()?;
+            Ok(Vec::new())
+        } else {
+            let from: Punctuated<Ident, Token![|]> = Punctuated::parse_separated_nonempty(input)?;
+            Ok(from.into_iter().collect())
+        }
+    }
+
+    /// `=>`, or `~>` for an internal transition
+    fn parse_arrow(input: ParseStream<'_>) -> Result<bool> {
+        if input.peek(Token![~]) {
+            let _: Token![~] = input.parse()?;
+            let _: Token![>] = input.parse()?;
+            Ok(true)
+        } else {
+            let _: Token![=>] = input.parse()?;
+            Ok(false)
+        }
+    }
+}
+
+impl Parse for TransitionPair {
+    /// example transition pairs:
+    ///
+    /// ```text
+    /// S1 => S2
+    /// S1 | S2 | S3 => S4
+    /// * => S5
+    /// S1 ~> S1
+    /// ```
+    ///
+    /// `~>` declares an internal transition: the machine stays in the state
+    /// and neither `exit()` nor `entry()` is called.
+    ///
+    /// `*` stands for every non-final state without its own transition on
+    /// the event.
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // `S1 | S2 => S3` or `* => S3`
+        //  ^^^^^^^            ^
+        let from = TransitionPair::parse_sources(input)?;
+
+        // `S1 => S2` or `S1 ~> S1`
+        //     ^^            ^^
+        let internal = TransitionPair::parse_arrow(input)?;
+
+        // `S1 => S2`
+        //        ^^
+        let to = Ident::parse(input)?;
+
+        TransitionPair::new(from, to, internal)
+    }
+}
+
+/// One row of the row-oriented transition table. `fsm!` names the event
+/// with an `Ident`, the `transitions!` front-end with its `Type`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct TransitionRow<E = Ident> {
+    pub event: E,
+    pub pairs: Vec<TransitionPair>,
+}
+
+impl<E: Parse> Parse for TransitionRow<E> {
+    /// example transition rows:
+    ///
+    /// ```text
+    /// (S1, EVENT1) => S2
+    /// (S1, EVENT1) => [S2, S3]
+    /// (S1 | S2, EVENT2) => S4
+    /// (S1, EVENT3) ~> S1
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // (S1, EVENT1) => S2
+        //  ^^^^^^^^^^
+        let row;
+        parenthesized!(row in input);
+        let from = TransitionPair::parse_sources(&row)?;
+        let _: Token![,] = row.parse()?;
+        let event: E = row.parse()?;
+
+        // (S1, EVENT1) => S2
+        //              ^^
+        let internal = TransitionPair::parse_arrow(input)?;
+
+        // (S1, EVENT1) => S2 or (S1, EVENT1) => [S2, S3]
+        //                 ^^                    ^^^^^^^^
+        let tos: Vec<Ident> = if input.peek(token::Bracket) {
+            let targets;
+            bracketed!(targets in input);
+            let tos: Punctuated<Ident, Token![,]> = targets.parse_terminated(Ident::parse)?;
+            tos.into_iter().collect()
+        } else {
+            vec![input.parse()?]
+        };
+
+        let pairs = tos
+            .into_iter()
+            .map(|to| TransitionPair::new(from.clone(), to, internal))
+            .collect::<Result<_>>()?;
+
+        Ok(TransitionRow { event, pairs })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Transition {
+    pub event_name: Ident,
+    pub pairs: BTreeMap<Ident, BTreeSet<Ident>>,
+    pub internal: BTreeSet<Ident>,
+    /// targets of `* => ...` lines, see `Transitions::expand_wildcards`
+    pub wildcard: BTreeSet<Ident>,
+    /// targets of lines repeating an earlier `from => to` pair
+    pub duplicates: Vec<Ident>,
+}
+
+impl Transition {
+    pub fn new(event_name: Ident) -> Self {
+        Transition {
+            event_name,
+            pairs: BTreeMap::new(),
+            internal: BTreeSet::new(),
+            wildcard: BTreeSet::new(),
+            duplicates: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, pair: TransitionPair) -> Result<()> {
+        if pair.from.is_empty() {
+            if self.wildcard.contains(&pair.to) {
+                self.duplicates.push(pair.to);
+            } else {
+                let _ = self.wildcard.insert(pair.to);
+            }
+            return Ok(());
+        }
+
+        for from in pair.from {
+            // an internal transition never leaves its state, so it cannot
+            // share the event with transitions that do
+            if pair.internal != self.internal.contains(&from) && self.pairs.contains_key(&from) {
+                return Err(syn::Error::new(
+                    from.span(),
+                    "internal and external transitions from the same state cannot share an event",
+                ));
+            }
+
+            if pair.internal {
+                let _ = self.internal.insert(from.clone());
+            }
+
+            let to = pair.to.clone();
+            if let Some(v) = self.pairs.get_mut(&from) {
+                if v.contains(&to) {
+                    self.duplicates.push(to);
+                } else {
+                    let _ = v.insert(to);
+                }
+            } else {
+                let mut v = BTreeSet::new();
+                let _ = v.insert(to);
+                let _ = self.pairs.insert(from, v);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `EVENT1 [ S1 => S2, S1 => S3, ]`
+    fn parse_block(input: ParseStream<'_>) -> Result<(Ident, Vec<TransitionPair>)> {
+        // EVENT1 [ ... ]
+        // ^^^^^^
+        let event_name: Ident = input.parse()?;
+
+        // EVENT1 [ ... ]
+        //          ^^^
+        let block_transition;
+        bracketed!(block_transition in input);
+
+        // EVENT1 [ S1 => S2, S1 => S3, ]
+        //          ^^^^^^^^^^^^^^^^^^^
+        let punctuated_block_transition: Punctuated<TransitionPair, Token![,]> =
+            block_transition.parse_terminated(TransitionPair::parse)?;
+
+        Ok((
+            event_name,
+            punctuated_block_transition.into_iter().collect(),
+        ))
+    }
+}
+
+impl Parse for Transition {
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let (event_name, pairs) = Transition::parse_block(input)?;
+
+        let mut transition = Transition::new(event_name);
+        for pair in pairs {
+            transition.add(pair)?;
+        }
+
+        Ok(transition)
+    }
+}
+
+/// Tokens opening the span of one `event()` call, tagged with
+/// `machine_name`. Expands to nothing unless the `tracing` feature is enabled.
+pub(crate) fn trace_span(machine_name: &str) -> TokenStream {
+    if !cfg!(feature = "tracing") {
+        return TokenStream::new();
+    }
+
+    quote! {
+        let __fsm_span = ::fsm_rs::__tracing::info_span!(
+            "fsm::event",
+            machine = #machine_name,
+            event = ::fsm_rs::__tracing::field::Empty,
+            from = ::fsm_rs::__tracing::field::Empty,
+            to = ::fsm_rs::__tracing::field::Empty,
+        );
+        let _enter = __fsm_span.enter();
+    }
+}
+
+/// Tokens recording `value` into `field` of the span opened by `event()`.
+/// Expands to nothing unless the `tracing` feature is enabled.
+fn trace_record(field: &str, value: &Ident) -> TokenStream {
+    if !cfg!(feature = "tracing") {
+        return TokenStream::new();
+    }
+
+    let value = value.to_string();
+    quote! {
+        let _ = __fsm_span.record(#field, &#value);
+    }
+}
+
+/// Like `trace_record`, for a `&str` known only at runtime.
+pub(crate) fn trace_record_expr(field: &str, value: TokenStream) -> TokenStream {
+    if !cfg!(feature = "tracing") {
+        return TokenStream::new();
+    }
+
+    quote! {
+        let _ = __fsm_span.record(#field, &#value);
+    }
+}
+
+/// Tokens emitting a `tracing` event at `level` with an optional `error`
+/// field. Expands to nothing unless the `tracing` feature is enabled.
+pub(crate) fn trace_event(level: &str, message: &str, error: Option<TokenStream>) -> TokenStream {
+    if !cfg!(feature = "tracing") {
+        return TokenStream::new();
+    }
+
+    let level = format_ident!("{}", level);
+    match error {
+        Some(error) => quote! {
+            ::fsm_rs::__tracing::#level!(error = #error, #message);
+        },
+        None => quote! {
+            ::fsm_rs::__tracing::#level!(#message);
+        },
+    }
+}
+
+/// Signature of the generated dispatch fn. With `Transactional;` it is the
+/// private `apply_event`, wrapped by `transactional_event_fn`.
+pub(crate) fn event_fn_signature(settings: &Settings) -> TokenStream {
+    if settings.transactional {
+        quote!(fn apply_event(&mut self, event: Event) -> Result<bool, ::fsm_rs::TransitionError>)
+    } else {
+        quote!(pub fn event(&mut self, event: Event) -> Result<bool, ::fsm_rs::TransitionError>)
+    }
+}
+
+/// `Machine::event` for `Transactional;` machines: snapshot everything
+/// `apply_event` may change and put it back if it fails.
+pub(crate) fn transactional_event_fn(settings: &Settings) -> TokenStream {
+    let (save_history, restore_history) = if settings.history.is_some() {
+        (
+            quote!(let history = self.history.clone();),
+            quote!(self.history = history;),
+        )
+    } else {
+        Default::default()
+    };
+
+    quote! {
+        /// Dispatch `event`. When it fails the state, the context and the
+        /// history are restored to what they were before the call, so it
+        /// either completes or changes nothing. Observer calls are not
+        /// undone.
+        pub fn event(&mut self, event: Event) -> Result<bool, ::fsm_rs::TransitionError> {
+            let state = self.current_state.clone();
+            let context = ::fsm_rs::Checkpoint::checkpoint(&self.context);
+            #save_history
+            let result = self.apply_event(event);
+            if result.is_err() {
+                self.current_state = state;
+                ::fsm_rs::Checkpoint::restore(&mut self.context, context);
+                #restore_history
+            }
+            result
+        }
+    }
+}
+
+struct AfterExitCase {
+    pub to: Ident,
+    pub history: bool,
+}
+
+impl ToTokens for AfterExitCase {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let to = &self.to;
+        let trace_to = trace_record("to", to);
+        let trace_transition = trace_event("debug", "transition", None);
+        let record = if self.history {
+            quote!(self.history.push(self.current_state.id(), event.id(), next.id());)
+        } else {
+            TokenStream::new()
+        };
+        tokens.extend(quote! {
+            next @ State::#to(_) => {
+                #trace_to
+                #trace_transition
+                #record
+                self.observer.on_transition(&self.current_state, &next, &event);
+                self.current_state = next;
+                self.enter(&event)
+            }
+        })
+    }
+}
+
+/// Name of the enum returned by the handler of `from` for `event_name` when
+/// it has several targets.
+pub(crate) fn target_enum_name(from: &Ident, event_name: &Ident) -> Ident {
+    format_ident!(
+        "{}{}Target",
+        from.to_string().to_camel_case(),
+        event_name.to_string().to_camel_case()
+    )
+}
+
+/// `pub enum <From><Event>Target`, with one variant per target holding the
+/// target's state type.
+pub(crate) fn target_enum_tokens(
+    from: &Ident,
+    event_name: &Ident,
+    tos: &[&Ident],
+    states: &States,
+) -> TokenStream {
+    let target = target_enum_name(from, event_name);
+    // `Machine::parse` rejects undeclared states, so nothing is dropped here
+    let (tos, to_types): (Vec<&Ident>, Vec<_>) = tos
+        .iter()
+        .filter_map(|v| Some((*v, &states.get(v)?.state_type)))
+        .unzip();
+    let doc = format!("Targets `{}` can move to on `{}`.", from, event_name);
+
+    quote! {
+        #[doc = #doc]
+        pub enum #target {
+            #( #tos(#to_types) ),*
+        }
+    }
+}
+
+/// Rejection of an event whose `exit()` returned a state it does not move to.
+pub(crate) const UNDECLARED_TARGET: &str = "exit() returned a state the event does not move to";
+
+struct StateCase {
+    pub event_name: Ident,
+    pub from: Ident,
+    pub tos: BTreeSet<Ident>,
+    pub internal: bool,
+    pub history: bool,
+    pub dispatch: Dispatch,
+}
+
+impl ToTokens for StateCase {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let from = &self.from;
+        let trace_from = trace_record("from", from);
+
+        if self.internal {
+            // internal transition: run the state's action for this event and
+            // stay put, skipping exit() and entry()
+            let action = format_ident!("on_{}", self.event_name.to_string().to_snake_case());
+            let trace_to = trace_record("to", from);
+            let trace_transition = trace_event("debug", "internal transition", None);
+            let record = if self.history {
+                quote!(self.history.push(StateId::#from, event.id(), StateId::#from);)
+            } else {
+                TokenStream::new()
+            };
+            tokens.extend(quote! {
+                State::#from(state) => {
+                    #trace_from
+                    #trace_to
+                    #trace_transition
+                    state.#action(data);
+                    #record
+                    self.observer.on_transition(&self.current_state, &self.current_state, &event);
+                    Ok(true)
+                }
+            });
+            return;
+        }
+
+        let trace_exit_failed = trace_event("warn", "state exit failed", Some(quote!(err)));
+
+        if self.dispatch == Dispatch::Handler || self.tos.len() > 1 {
+            // the handler picks the target, returning the restricted
+            // `<From><Event>Target` enum when there are several, so exit()
+            // can only refuse the transition. It runs first, so a refusal
+            // leaves the context as the handler would have found it
+            let handler = format_ident!("on_{}", self.event_name.to_string().to_snake_case());
+            let tos: Vec<_> = self.tos.iter().collect();
+            let next = if tos.len() == 1 {
+                let to = tos[0];
+                quote!(State::#to(state.#handler(&mut self.context, data.clone())))
+            } else {
+                let target = target_enum_name(from, &self.event_name);
+                quote! {
+                    match state.#handler(&mut self.context, data.clone()) {
+                        #( #target::#tos(next) => State::#tos(next), )*
+                    }
+                }
+            };
+            let exit = quote! {
+                if let Err(err) = state.exit() {
+                    #trace_exit_failed
+                    let err = ::fsm_rs::TransitionError::Rejected(err);
+                    self.observer.on_rejected(&self.current_state, &event, &err);
+                    return Err(err);
+                }
+            };
+            let trace_to = trace_record_expr("to", quote!(next.id().name()));
+            let trace_transition = trace_event("debug", "transition", None);
+            let record = if self.history {
+                quote!(self.history.push(StateId::#from, event.id(), next.id());)
+            } else {
+                TokenStream::new()
+            };
+            tokens.extend(quote! {
+                State::#from(state) => {
+                    #trace_from
+                    #exit
+                    let next = #next;
+                    #trace_to
+                    #trace_transition
+                    #record
+                    self.observer.on_transition(&self.current_state, &next, &event);
+                    self.current_state = next;
+                    self.enter(&event)
+                }
+            });
+            return;
+        }
+
+        let trace_undeclared_target = trace_event("warn", UNDECLARED_TARGET, None);
+        let after_exit_cases: Vec<_> = self
+            .tos
+            .iter()
+            .map(|v| AfterExitCase {
+                to: v.clone(),
+                history: self.history,
+            })
+            .collect();
+
+        tokens.extend(quote! {
+            State::#from(state) => {
+                #trace_from
+                match state.exit() {
+                    Ok(r) =>  {
+                        #[allow(unreachable_patterns)]
+                        match r {
+                            #( #after_exit_cases )*
+                            _ => {
+                                #trace_undeclared_target
+                                let err = ::fsm_rs::TransitionError::Rejected(#UNDECLARED_TARGET);
+                                self.observer.on_rejected(&self.current_state, &event, &err);
+                                Err(err)
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        #trace_exit_failed
+                        let err = ::fsm_rs::TransitionError::Rejected(err);
+                        self.observer.on_rejected(&self.current_state, &event, &err);
+                        Err(err)
+                    }
+                }
+            }
+        })
+    }
+}
+
+struct EventCase {
+    pub event_name: Ident,
+    pub pairs: BTreeMap<Ident, BTreeSet<Ident>>,
+    pub internal: BTreeSet<Ident>,
+    pub history: bool,
+    pub dispatch: Dispatch,
+}
+
+impl ToTokens for EventCase {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let event_name = &self.event_name;
+
+        let state_cases: Vec<_> = self
+            .pairs
+            .iter()
+            .map(|v| StateCase {
+                event_name: event_name.clone(),
+                from: v.0.clone(),
+                tos: v.1.clone(),
+                internal: self.internal.contains(v.0),
+                history: self.history,
+                dispatch: self.dispatch,
+            })
+            .collect();
+
+        let trace_event_name = trace_record("event", event_name);
+        let trace_guard_failed = trace_event("warn", "event guard failed", Some(quote!(err)));
+        let trace_unhandled = trace_event("debug", "event not handled", None);
+
+        tokens.extend(quote! {
+            Event::#event_name(data) => {
+                #trace_event_name
+                if let Err(err) = data.on() {
+                    #trace_guard_failed
+                    let err = ::fsm_rs::TransitionError::Rejected(err);
+                    self.observer.on_rejected(&self.current_state, &event, &err);
+                    return Err(err);
+                }
+                #[allow(unreachable_patterns)]
+                match &mut self.current_state {
+                    #( #state_cases )*
+                    _ => {
+                        #trace_unhandled
+                        self.observer.on_unhandled(&self.current_state, &event);
+                        Ok(false)
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Transitions(
+    pub Vec<Transition>,
+    /// `S2 on_error => Failed` lines: the state entered when `entry()` of
+    /// `S2` fails, see also `Transitions::expand_error_state`
+    pub BTreeMap<Ident, Ident>,
+);
+
+impl Parse for Transitions {
+    /// example transitions tokens:
+    ///
+    /// ```text
+    /// Transitions {
+    ///     EVENT1 [
+    ///         S1 => S2,
+    ///         S1 => S3,
+    ///     ],
+    ///
+    ///     EVENT2 [
+    ///         S4 => S5,
+    ///     ],
+    /// }
+    /// ```
+    ///
+    /// or, row-oriented:
+    ///
+    /// ```text
+    /// Transitions {
+    ///     (S1, EVENT1) => [S2, S3],
+    ///     (S4, EVENT2) => S5,
+    /// }
+    /// ```
+    ///
+    /// Both forms may be mixed, everything declared for one event is merged
+    /// into a single `Transition`. Lines of the form
+    ///
+    /// ```text
+    /// S2 on_error => Failed,
+    /// ```
+    ///
+    /// route a failed `entry()` of `S2` to `Failed`.
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // Transitions { ... }
+        // -----------
+        let magic = Ident::parse(input)?;
+
+        if magic != "Transitions" {
+            return Err(input.error("expected Transitions { ... }"));
+        }
+
+        let content;
+        braced!(content in input);
+
+        let mut transitions: Vec<Transition> = Vec::new();
+        let mut error_routes = BTreeMap::new();
+
+        while !content.is_empty() {
+            if content.peek(Ident) && content.peek2(Ident) {
+                // S2 on_error => Failed
+                let from: Ident = content.parse()?;
+                let magic: Ident = content.parse()?;
+                if magic != "on_error" {
+                    return Err(syn::Error::new(magic.span(), "expected S on_error => T"));
+                }
+                let _: Token![=>] = content.parse()?;
+                let to: Ident = content.parse()?;
+
+                if error_routes.contains_key(&from) {
+                    return Err(syn::Error::new(
+                        from.span(),
+                        format!("duplicate on_error route for `{}`", from),
+                    ));
+                }
+                let _ = error_routes.insert(from, to);
+
+                if content.is_empty() {
+                    break;
+                }
+                let _: Token![,] = content.parse()?;
+                continue;
+            }
+
+            let (event_name, pairs) = if content.peek(token::Paren) {
+                let row: TransitionRow = content.parse()?;
+                (row.event, row.pairs)
+            } else {
+                Transition::parse_block(&content)?
+            };
+
+            let index = match transitions.iter().position(|v| v.event_name == event_name) {
+                Some(index) => index,
+                None => {
+                    transitions.push(Transition::new(event_name));
+                    transitions.len() - 1
+                }
+            };
+            for pair in pairs {
+                transitions[index].add(pair)?;
+            }
+
+            if content.is_empty() {
+                break;
+            }
+            let _: Token![,] = content.parse()?;
+        }
+
+        Ok(Transitions(transitions, error_routes))
+    }
+}
+
+impl Transitions {
+    /// Give every non-final state without its own transition on an event the
+    /// targets of that event's `* => ...` lines.
+    pub fn expand_wildcards(&mut self, states: &States, finals: &FinalStates) {
+        for transition in &mut self.0 {
+            if transition.wildcard.is_empty() {
+                continue;
+            }
+
+            for state in &states.0 {
+                let name = &state.state_name;
+                if !finals.contains(name) && !transition.pairs.contains_key(name) {
+                    let _ = transition
+                        .pairs
+                        .insert(name.clone(), transition.wildcard.clone());
+                }
+            }
+        }
+    }
+
+    /// Route failed entries of every state without its own `on_error` line
+    /// to the `ErrorState = ...;` setting.
+    pub fn expand_error_state(&mut self, states: &States, error_state: &Ident) {
+        for state in &states.0 {
+            let name = &state.state_name;
+            if name != error_state && !self.1.contains_key(name) {
+                let _ = self.1.insert(name.clone(), error_state.clone());
+            }
+        }
+    }
+
+    /// The states whose `entry()` the generated dispatch calls: targets of
+    /// external transitions and of `on_error` routes.
+    pub fn entered(&self) -> BTreeSet<&Ident> {
+        self.0
+            .iter()
+            .flat_map(|v| {
+                v.pairs
+                    .iter()
+                    .filter(move |(from, _)| !v.internal.contains(*from))
+                    .flat_map(|(_, tos)| tos)
+            })
+            .chain(self.1.values())
+            .collect()
+    }
+
+    /// Generate the queries answering which events the current state
+    /// accepts: `can_fire`, `permitted_events`, `can_handle` and
+    /// `can_fire_with`, the latter also evaluating the event guard.
+    pub fn to_query_fn_tokens(&self) -> TokenStream {
+        let cases: Vec<_> = self
+            .0
+            .iter()
+            .flat_map(|transition| {
+                let event_name = &transition.event_name;
+                transition
+                    .pairs
+                    .keys()
+                    .map(move |from| quote!((StateId::#from, EventId::#event_name)))
+            })
+            .collect();
+
+        let can_fire = if cases.is_empty() {
+            quote!(false)
+        } else {
+            quote! {
+                #[allow(unreachable_patterns)]
+                match (self.current_state.id(), event) {
+                    #( #cases )|* => true,
+                    _ => false,
+                }
+            }
+        };
+
+        let guarded: Vec<_> = self
+            .0
+            .iter()
+            .filter(|v| !v.pairs.is_empty())
+            .map(|v| &v.event_name)
+            .collect();
+
+        quote! {
+            /// Whether a transition is declared for `event` in the current
+            /// state. Guards are not evaluated.
+            pub fn can_fire(&self, event: EventId) -> bool {
+                #can_fire
+            }
+
+            /// The events for which the current state declares a transition.
+            pub fn permitted_events(&self) -> impl Iterator<Item = EventId> + '_ {
+                EventId::ALL.iter().copied().filter(move |v| self.can_fire(*v))
+            }
+
+            /// Like `can_fire`, taking the event itself.
+            pub fn can_handle(&self, event: &Event) -> bool {
+                self.can_fire(event.id())
+            }
+
+            /// Whether `event` would be accepted: a transition is declared
+            /// for it and its guard passes. Nothing is mutated, `exit()` is
+            /// not called.
+            pub fn can_fire_with(&self, event: &Event) -> bool {
+                if !self.can_handle(event) {
+                    return false;
+                }
+
+                #[allow(unreachable_patterns)]
+                match event {
+                    #( Event::#guarded(data) => data.on().is_ok(), )*
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    /// Generate the `<From><Event>Target` enums returned by the handlers of
+    /// transitions with several targets. Only the declared targets are
+    /// variants, so picking another one does not compile.
+    pub fn to_target_enum_tokens(&self, states: &States) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        for transition in &self.0 {
+            for (from, tos) in &transition.pairs {
+                if tos.len() > 1 && !transition.internal.contains(from) {
+                    let tos: Vec<_> = tos.iter().collect();
+                    tokens.extend(target_enum_tokens(
+                        from,
+                        &transition.event_name,
+                        &tos,
+                        states,
+                    ));
+                }
+            }
+        }
+        tokens
+    }
+
+    /// Generate `Machine::preview`, mirroring the matches of `event()` but
+    /// only calling the event guard.
+    pub fn to_preview_fn_tokens(&self, settings: &Settings) -> TokenStream {
+        let event_cases = self.0.iter().filter(|v| !v.pairs.is_empty()).map(|transition| {
+            let event_name = &transition.event_name;
+
+            let state_cases = transition.pairs.iter().map(|(from, tos)| {
+                let internal = transition.internal.contains(from);
+                let callbacks = if internal {
+                    quote! {
+                        ::fsm_rs::Callback::Guard(EventId::#event_name),
+                        ::fsm_rs::Callback::Action(StateId::#from, EventId::#event_name),
+                    }
+                } else if settings.dispatch == Dispatch::Handler || tos.len() > 1 {
+                    quote! {
+                        ::fsm_rs::Callback::Guard(EventId::#event_name),
+                        ::fsm_rs::Callback::Exit(StateId::#from),
+                        ::fsm_rs::Callback::Action(StateId::#from, EventId::#event_name),
+                        #( ::fsm_rs::Callback::Entry(StateId::#tos), )*
+                    }
+                } else {
+                    quote! {
+                        ::fsm_rs::Callback::Guard(EventId::#event_name),
+                        ::fsm_rs::Callback::Exit(StateId::#from),
+                        #( ::fsm_rs::Callback::Entry(StateId::#tos), )*
+                    }
+                };
+                quote! {
+                    State::#from(_) => (#internal, &[#( StateId::#tos ),*], &[#callbacks]),
+                }
+            });
+
+            quote! {
+                Event::#event_name(data) => {
+                    let guard = data.on();
+                    #[allow(unreachable_patterns)]
+                    let (internal, targets, callbacks): (bool, &'static [StateId], &'static [::fsm_rs::Callback<StateId, EventId>]) =
+                        match &self.current_state {
+                            #( #state_cases )*
+                            _ => (false, &[], &[::fsm_rs::Callback::Guard(EventId::#event_name)]),
+                        };
+                    ::fsm_rs::Preview {
+                        terminated: false,
+                        internal,
+                        targets,
+                        // nothing runs after a failed guard
+                        callbacks: if guard.is_ok() { callbacks } else { &callbacks[..1] },
+                        guard: Some(guard),
+                    }
+                }
+            }
+        });
+
+        quote! {
+            /// What `event()` would do with `event`: the declared targets,
+            /// the guard result and the callbacks it would call. Only the
+            /// guard is run, the machine is left unchanged.
+            pub fn preview(&self, event: &Event) -> ::fsm_rs::Preview<StateId, EventId> {
+                let unhandled = ::fsm_rs::Preview {
+                    terminated: self.is_terminated(),
+                    guard: None,
+                    internal: false,
+                    targets: &[],
+                    callbacks: &[],
+                };
+                if unhandled.terminated {
+                    return unhandled;
+                }
+
+                #[allow(unreachable_patterns)]
+                match event {
+                    #( #event_cases )*
+                    _ => unhandled,
+                }
+            }
+        }
+    }
+
+    /// Generate `Machine::enter`, called by `event()` once the machine moved
+    /// to its new state. A failed `entry()` moves on to the state's
+    /// `on_error` target, passing the error into its payload, or rejects the
+    /// event. Nothing is generated when no state is ever entered.
+    pub fn to_enter_fn_tokens(&self, settings: &Settings) -> TokenStream {
+        let entered = self.entered();
+        if entered.is_empty() {
+            return TokenStream::new();
+        }
+
+        let trace_entry_failed = trace_event("warn", "state entry failed", Some(quote!(err)));
+        let reject = quote! {
+            let err = ::fsm_rs::TransitionError::Rejected(err);
+            self.observer.on_rejected(&self.current_state, event, &err);
+            Err(err)
+        };
+
+        let route = if self.1.is_empty() {
+            reject
+        } else {
+            let froms = self.1.keys();
+            let tos = self.1.values();
+            let targets: BTreeSet<_> = self.1.values().collect();
+            // enter() runs inside the span entered by event()
+            let trace_span = if cfg!(feature = "tracing") {
+                quote!(let __fsm_span = ::fsm_rs::__tracing::Span::current();)
+            } else {
+                TokenStream::new()
+            };
+            let trace_to = trace_record_expr("to", quote!(failed.id().name()));
+            let trace_transition = trace_event("debug", "routed to error state", None);
+            let record = if settings.history.is_some() {
+                quote!(self.history.push(self.current_state.id(), event.id(), failed.id());)
+            } else {
+                TokenStream::new()
+            };
+
+            quote! {
+                #[allow(unreachable_patterns)]
+                let failed = match &self.current_state {
+                    #( State::#froms(_) => State::#tos(::core::convert::From::from(err)), )*
+                    _ => return { #reject },
+                };
+                #trace_span
+                #trace_to
+                #trace_transition
+                #record
+                self.observer.on_transition(&self.current_state, &failed, event);
+                self.current_state = failed;
+
+                // the error state's own entry is not routed again
+                #[allow(unreachable_patterns)]
+                let entered = match &mut self.current_state {
+                    #( State::#targets(state) => ::fsm_rs::EntryResult::into_result(state.entry()), )*
+                    _ => Ok(()),
+                };
+                match entered {
+                    Ok(()) => Ok(true),
+                    Err(err) => {
+                        #trace_entry_failed
+                        #reject
+                    }
+                }
+            }
+        };
+
+        quote! {
+            fn enter(&mut self, event: &Event) -> Result<bool, ::fsm_rs::TransitionError> {
+                #[allow(unreachable_patterns)]
+                let entered = match &mut self.current_state {
+                    #( State::#entered(state) => ::fsm_rs::EntryResult::into_result(state.entry()), )*
+                    _ => Ok(()),
+                };
+                let err = match entered {
+                    Ok(()) => return Ok(true),
+                    Err(err) => err,
+                };
+                #trace_entry_failed
+
+                #route
+            }
+        }
+    }
+
+    /// Generate `Machine::event`. `machine_name` tags the span opened per
+    /// call when the `tracing` feature is enabled.
+    pub fn to_event_fn_tokens(&self, machine_name: &str, settings: &Settings) -> TokenStream {
+        let event_cases: Vec<_> = self
+            .0
+            .iter()
+            .map(|v| EventCase {
+                event_name: v.event_name.clone(),
+                pairs: v.pairs.clone(),
+                internal: v.internal.clone(),
+                history: settings.history.is_some(),
+                dispatch: settings.dispatch,
+            })
+            .collect();
+
+        let trace_span = trace_span(machine_name);
+        let trace_unhandled = trace_event("debug", "event not handled", None);
+        let trace_terminated = trace_event("warn", "machine is terminated", None);
+        let signature = event_fn_signature(settings);
+
+        quote! {
+            #signature {
+                #trace_span
+                self.observer.on_event_received(&self.current_state, &event);
+
+                if self.is_terminated() {
+                    #trace_terminated
+                    let err = ::fsm_rs::TransitionError::Terminated;
+                    self.observer.on_rejected(&self.current_state, &event, &err);
+                    return Err(err);
+                }
+
+                #[allow(unreachable_patterns)]
+                match &event {
+                    #( #event_cases )*
+                    _ => {
+                        #trace_unhandled
+                        self.observer.on_unhandled(&self.current_state, &event);
+                        Ok(false)
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn;
+
+    #[test]
+    fn test_transition_parse_internal() {
+        let transition: Transition = syn::parse2(quote! {
+            EVENT1 [
+               S1 ~> S1,
+               S2 => S2,
+            ]
+        })
+        .unwrap();
+
+        assert!(transition.internal.contains(&format_ident!("S1")));
+        assert!(!transition.internal.contains(&format_ident!("S2")));
+
+        assert!(syn::parse2::<Transition>(quote! {
+            EVENT1 [
+               S1 ~> S2,
+            ]
+        })
+        .is_err());
+
+        assert!(syn::parse2::<Transition>(quote! {
+            EVENT1 [
+               S1 ~> S1,
+               S1 => S2,
+            ]
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_transitions_parse_rows() {
+        let rows: Transitions = syn::parse2(quote! {
+            Transitions {
+                (S1, E1) => [S2, S3],
+                (S2 | S3, E2) => S1,
+                E1 [
+                    S2 => S3,
+                ],
+                (S3, E3) ~> S3,
+            }
+        })
+        .unwrap();
+        let blocks: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [
+                    S1 => S2,
+                    S1 => S3,
+                    S2 => S3,
+                ],
+                E2 [
+                    S2 | S3 => S1,
+                ],
+                E3 [
+                    S3 ~> S3,
+                ],
+            }
+        })
+        .unwrap();
+
+        assert_eq!(rows, blocks);
+    }
+
+    #[test]
+    fn test_transitions_parse_error_routes() {
+        let states: States = syn::parse2(quote! {
+            States { S1 = S1, S2 = S2, S3 = S3, Failed = Failed }
+        })
+        .unwrap();
+        let mut transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [ S1 => S2 ],
+                S2 on_error => S3,
+                E2 [ S2 ~> S2 ],
+            }
+        })
+        .unwrap();
+        assert_eq!(transitions.0.len(), 2);
+        assert_eq!(transitions.1.len(), 1);
+        assert_eq!(transitions.1[&format_ident!("S2")], "S3");
+
+        transitions.expand_error_state(&states, &format_ident!("Failed"));
+        let routes: Vec<_> = transitions
+            .1
+            .iter()
+            .map(|(from, to)| format!("{} => {}", from, to))
+            .collect();
+        assert_eq!(routes, vec!["S1 => Failed", "S2 => S3", "S3 => Failed"]);
+
+        let entered: Vec<_> = transitions.entered().into_iter().collect();
+        assert_eq!(entered, vec!["Failed", "S2", "S3"]);
+
+        assert!(syn::parse2::<Transitions>(quote! {
+            Transitions {
+                S2 on_error => S3,
+                S2 on_error => S1,
+            }
+        })
+        .is_err());
+
+        assert!(syn::parse2::<Transitions>(quote! {
+            Transitions {
+                S2 on_exit => S3,
+            }
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_transitions_expand_sources() {
+        let mut transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [
+                    S1 | S2 => S3,
+                    * => S4,
+                    S1 => S2,
+                ]
+            }
+        })
+        .unwrap();
+        let states: States = syn::parse2(quote! {
+            States {
+                S1 = S1,
+                S2 = S2,
+                S3 = S3,
+                S4 = S4,
+                S5 = S5
+            }
+        })
+        .unwrap();
+        let finals: FinalStates = syn::parse2(quote!(Final { S5 })).unwrap();
+
+        transitions.expand_wildcards(&states, &finals);
+
+        let pairs: Vec<_> = transitions.0[0]
+            .pairs
+            .iter()
+            .map(|(from, tos)| {
+                let tos: Vec<_> = tos.iter().map(|v| v.to_string()).collect();
+                (from.to_string(), tos.join(" "))
+            })
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("S1".to_string(), "S2 S3".to_string()),
+                ("S2".to_string(), "S3".to_string()),
+                ("S3".to_string(), "S4".to_string()),
+                ("S4".to_string(), "S4".to_string()),
+            ]
+        );
+
+        assert!(syn::parse2::<Transition>(quote! {
+            E1 [
+               S1 | S2 ~> S1,
+            ]
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_transitions_event_fn_restricted_targets() {
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [
+                    S1 => S2,
+                    S1 => S3,
+                    S2 => S3,
+                ]
+            }
+        })
+        .unwrap();
+
+        let tokens = transitions
+            .to_event_fn_tokens("FSM", &Settings::default())
+            .to_string();
+
+        // S1 has several targets on E1: its handler picks one of the
+        // restricted enum, exit() can only refuse, before the handler runs
+        let handler = quote! {
+            let next = match state.on_e1(&mut self.context, data.clone()) {
+                S1E1Target::S2(next) => State::S2(next),
+                S1E1Target::S3(next) => State::S3(next),
+            };
+        };
+        let refuse = quote!(if let Err(err) = state.exit());
+        let handler_at = tokens.find(&handler.to_string()).unwrap();
+        let refuse_at = tokens.find(&refuse.to_string()).unwrap();
+        assert!(refuse_at < handler_at);
+
+        // S2 has a single target, still picked by exit(), which is rejected
+        // when it returns another state
+        let exit = quote!(match state.exit());
+        assert_eq!(tokens.matches(&exit.to_string()).count(), 1);
+        let undeclared = quote!(::fsm_rs::TransitionError::Rejected(#UNDECLARED_TARGET));
+        assert_eq!(tokens.matches(&undeclared.to_string()).count(), 1);
+        assert!(!tokens.contains("panic"));
+    }
+
+    #[test]
+    fn test_transitions_query_fn_tokens() {
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [
+                    S1 => S2,
+                    S2 ~> S2,
+                ]
+            }
+        })
+        .unwrap();
+
+        let left = quote! {
+            /// Whether a transition is declared for `event` in the current
+            /// state. Guards are not evaluated.
+            pub fn can_fire(&self, event: EventId) -> bool {
+                #[allow(unreachable_patterns)]
+                match (self.current_state.id(), event) {
+                    (StateId::S1, EventId::E1) | (StateId::S2, EventId::E1) => true,
+                    _ => false,
+                }
+            }
+
+            /// The events for which the current state declares a transition.
+            pub fn permitted_events(&self) -> impl Iterator<Item = EventId> + '_ {
+                EventId::ALL.iter().copied().filter(move |v| self.can_fire(*v))
+            }
+
+            /// Like `can_fire`, taking the event itself.
+            pub fn can_handle(&self, event: &Event) -> bool {
+                self.can_fire(event.id())
+            }
+
+            /// Whether `event` would be accepted: a transition is declared
+            /// for it and its guard passes. Nothing is mutated, `exit()` is
+            /// not called.
+            pub fn can_fire_with(&self, event: &Event) -> bool {
+                if !self.can_handle(event) {
+                    return false;
+                }
+
+                #[allow(unreachable_patterns)]
+                match event {
+                    Event::E1(data) => data.on().is_ok(),
+                    _ => false,
+                }
+            }
+        };
+
+        let right = transitions.to_query_fn_tokens();
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+    }
+
+    #[test]
+    fn test_transitions_preview_fn_tokens() {
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [
+                    S1 => S2,
+                    S2 ~> S2,
+                ]
+            }
+        })
+        .unwrap();
+
+        let left = quote! {
+            /// What `event()` would do with `event`: the declared targets,
+            /// the guard result and the callbacks it would call. Only the
+            /// guard is run, the machine is left unchanged.
+            pub fn preview(&self, event: &Event) -> ::fsm_rs::Preview<StateId, EventId> {
+                let unhandled = ::fsm_rs::Preview {
+                    terminated: self.is_terminated(),
+                    guard: None,
+                    internal: false,
+                    targets: &[],
+                    callbacks: &[],
+                };
+                if unhandled.terminated {
+                    return unhandled;
+                }
+
+                #[allow(unreachable_patterns)]
+                match event {
+                    Event::E1(data) => {
+                        let guard = data.on();
+                        #[allow(unreachable_patterns)]
+                        let (internal, targets, callbacks): (bool, &'static [StateId], &'static [::fsm_rs::Callback<StateId, EventId>]) =
+                            match &self.current_state {
+                                State::S1(_) => (false, &[StateId::S2], &[
+                                    ::fsm_rs::Callback::Guard(EventId::E1),
+                                    ::fsm_rs::Callback::Exit(StateId::S1),
+                                    ::fsm_rs::Callback::Entry(StateId::S2),
+                                ]),
+                                State::S2(_) => (true, &[StateId::S2], &[
+                                    ::fsm_rs::Callback::Guard(EventId::E1),
+                                    ::fsm_rs::Callback::Action(StateId::S2, EventId::E1),
+                                ]),
+                                _ => (false, &[], &[::fsm_rs::Callback::Guard(EventId::E1)]),
+                            };
+                        ::fsm_rs::Preview {
+                            terminated: false,
+                            internal,
+                            targets,
+                            callbacks: if guard.is_ok() { callbacks } else { &callbacks[..1] },
+                            guard: Some(guard),
+                        }
+                    }
+                    _ => unhandled,
+                }
+            }
+        };
+
+        let right = transitions.to_preview_fn_tokens(&Settings::default());
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+    }
+
+    #[test]
+    fn test_transitions_target_enum_tokens() {
+        let states: States = syn::parse2(quote! {
+            States { S1 = S1, S2 = S2, S3 = Three }
+        })
+        .unwrap();
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [
+                    S1 => S2,
+                    S1 => S3,
+                    S2 => S3,
+                ],
+                E2 [ S2 ~> S2 ]
+            }
+        })
+        .unwrap();
+
+        let left = quote! {
+            #[doc = "Targets `S1` can move to on `E1`."]
+            pub enum S1E1Target {
+                S2(S2),
+                S3(Three)
+            }
+        };
+
+        let right = transitions.to_target_enum_tokens(&states);
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+    }
+
+    #[test]
+    fn test_transitions_enter_fn_tokens() {
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [
+                    S1 => S2,
+                    S2 ~> S2,
+                ]
+            }
+        })
+        .unwrap();
+
+        // empty unless the `tracing` feature is enabled
+        let trace_entry_failed = trace_event("warn", "state entry failed", Some(quote!(err)));
+
+        let left = quote! {
+            fn enter(&mut self, event: &Event) -> Result<bool, ::fsm_rs::TransitionError> {
+                #[allow(unreachable_patterns)]
+                let entered = match &mut self.current_state {
+                    State::S2(state) => ::fsm_rs::EntryResult::into_result(state.entry()),
+                    _ => Ok(()),
+                };
+                let err = match entered {
+                    Ok(()) => return Ok(true),
+                    Err(err) => err,
+                };
+                #trace_entry_failed
+                let err = ::fsm_rs::TransitionError::Rejected(err);
+                self.observer.on_rejected(&self.current_state, event, &err);
+                Err(err)
+            }
+        };
+
+        let right = transitions.to_enter_fn_tokens(&Settings::default());
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+
+        let internal_only: Transitions = syn::parse2(quote! {
+            Transitions {
+                E1 [ S2 ~> S2 ]
+            }
+        })
+        .unwrap();
+        assert!(internal_only
+            .to_enter_fn_tokens(&Settings::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_transactional_event_fn_tokens() {
+        let settings: Settings = syn::parse2(quote! {
+            Transactional;
+        })
+        .unwrap();
+
+        let left = quote! {
+            /// Dispatch `event`. When it fails the state, the context and the
+            /// history are restored to what they were before the call, so it
+            /// either completes or changes nothing. Observer calls are not
+            /// undone.
+            pub fn event(&mut self, event: Event) -> Result<bool, ::fsm_rs::TransitionError> {
+                let state = self.current_state.clone();
+                let context = ::fsm_rs::Checkpoint::checkpoint(&self.context);
+                let result = self.apply_event(event);
+                if result.is_err() {
+                    self.current_state = state;
+                    ::fsm_rs::Checkpoint::restore(&mut self.context, context);
+                }
+                result
+            }
+        };
+
+        let right = transactional_event_fn(&settings);
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+        assert_eq!(
+            format!("{}", event_fn_signature(&settings)),
+            format!(
+                "{}",
+                quote!(fn apply_event(&mut self, event: Event) -> Result<bool, ::fsm_rs::TransitionError>)
+            )
+        );
+    }
+}
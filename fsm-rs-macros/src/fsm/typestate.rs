@@ -0,0 +1,313 @@
+use heck::{CamelCase, SnakeCase};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse::Result, Type};
+
+use crate::fsm::{
+    events::Events,
+    settings::Settings,
+    states::{FinalStates, States},
+    transitions::{target_enum_name, target_enum_tokens, Transitions},
+};
+
+/// Typestate machines are keyed by the state types, so every state needs its
+/// own type and per-machine runtime features are not available.
+pub(crate) fn check(states: &States, history: bool) -> Result<()> {
+    if history {
+        return Err(syn::Error::new(
+            states.initial().state_name.span(),
+            "History is not supported with Mode = Typestate",
+        ));
+    }
+
+    for (i, state) in states.0.iter().enumerate() {
+        if let Some(other) = states.0[..i]
+            .iter()
+            .find(|v| v.state_type == state.state_type)
+        {
+            return Err(syn::Error::new(
+                state.state_name.span(),
+                format!(
+                    "`{}` has the same type as `{}`, typestate machines need one type per state",
+                    state.state_name, other.state_name
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Generates `Machine<S>` and, for every state, the consuming methods of the
+/// transitions leaving it. Each method hands the event to
+/// `S::on_<event>(self, &mut Context, Event)`, which returns the target state
+/// or, for several targets, the generated `<State><Event>Target` enum.
+/// Internal `~>` transitions borrow the machine instead and run the same
+/// `S::on_<event>(&mut self, &Event)` action as `Mode = Match;`.
+///
+/// Unlike `Mode = Match;`, the methods never call the event's `on()` guard or
+/// the states' `exit()` and `entry()` hooks: the types already rule out the
+/// transitions a state does not have, and the handlers do the rest.
+pub(crate) fn to_tokens(
+    context_type: &Type,
+    states: &States,
+    finals: &FinalStates,
+    events: &Events,
+    transitions: &Transitions,
+    settings: &Settings,
+) -> TokenStream {
+    let initial_type = &states.initial().state_type;
+    let (constness, context_init, state_init) =
+        settings.constructor_tokens(context_type, initial_type);
+
+    let state_type = |name| states.get(name).map(|v| &v.state_type);
+
+    let mut tokens = TokenStream::new();
+
+    for state in &states.0 {
+        let from = &state.state_name;
+        let from_type = &state.state_type;
+
+        let methods = transitions.0.iter().filter_map(|transition| {
+            let tos = transition.pairs.get(from)?;
+            let event_name = &transition.event_name;
+            let event_type = &events.get(event_name)?.event_type;
+            let method = format_ident!("{}", event_name.to_string().to_snake_case());
+            let handler = format_ident!("on_{}", method);
+
+            if transition.internal.contains(from) {
+                return Some(quote! {
+                    pub fn #method(&mut self, event: &#event_type) {
+                        self.state.#handler(event);
+                    }
+                });
+            }
+
+            if tos.len() == 1 {
+                let to_type = state_type(tos.iter().next()?)?;
+                return Some(quote! {
+                    pub fn #method(mut self, event: #event_type) -> Machine<#to_type> {
+                        let state = self.state.#handler(&mut self.context, event);
+                        Machine {
+                            context: self.context,
+                            state,
+                        }
+                    }
+                });
+            }
+
+            let name = format_ident!(
+                "{}{}",
+                from.to_string().to_camel_case(),
+                event_name.to_string().to_camel_case()
+            );
+            let target = target_enum_name(from, event_name);
+            let tos: Vec<_> = tos.iter().collect();
+            let to_types: Vec<_> = tos.iter().map(|v| state_type(v)).collect::<Option<_>>()?;
+            let target_enum = target_enum_tokens(from, event_name, &tos, states);
+            let doc_machine = format!("`Machine` after `{}` handled `{}`.", from, event_name);
+
+            tokens.extend(quote! {
+                #target_enum
+
+                #[doc = #doc_machine]
+                pub enum #name {
+                    #( #tos(Machine<#to_types>) ),*
+                }
+            });
+
+            Some(quote! {
+                pub fn #method(mut self, event: #event_type) -> #name {
+                    let target = self.state.#handler(&mut self.context, event);
+                    let context = self.context;
+                    match target {
+                        #( #target::#tos(state) => #name::#tos(Machine { context, state }), )*
+                    }
+                }
+            })
+        });
+        let methods: Vec<_> = methods.collect();
+
+        let into_output_fn = match &settings.output {
+            Some(output) if finals.contains(from) => quote! {
+                /// Consume the terminated machine and convert its final
+                /// state into the output.
+                pub fn into_output(self) -> #output {
+                    self.state.into()
+                }
+            },
+            _ => TokenStream::new(),
+        };
+
+        if !methods.is_empty() || !into_output_fn.is_empty() {
+            tokens.extend(quote! {
+                impl Machine<#from_type> {
+                    #( #methods )*
+
+                    #into_output_fn
+                }
+            });
+        }
+    }
+
+    quote! {
+        /// State machine in state `S`. Only the transitions declared for `S`
+        /// exist as methods, so illegal sequences do not compile.
+        pub struct Machine<S> {
+            context: #context_type,
+            state: S,
+        }
+
+        impl Machine<#initial_type> {
+            pub #constness fn new() -> Machine<#initial_type> {
+                Machine {
+                    context: #context_init,
+                    state: #state_init,
+                }
+            }
+        }
+
+        impl<S> Machine<S> {
+            pub #constness fn context(&self) -> &#context_type {
+                &self.context
+            }
+
+            pub #constness fn state(&self) -> &S {
+                &self.state
+            }
+
+            pub fn into_parts(self) -> (#context_type, S) {
+                (self.context, self.state)
+            }
+        }
+
+        #tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_typestate_to_tokens() {
+        let states: States = syn::parse2(quote! {
+            States {
+                Green = Green,
+                Orange = Orange,
+                Red = Red
+            }
+        })
+        .unwrap();
+        let finals: FinalStates = syn::parse2(quote!(Final { Red })).unwrap();
+        let events: Events = syn::parse2(quote! {
+            Events {
+                Advance = Advance,
+                Poke = Poke
+            }
+        })
+        .unwrap();
+        let transitions: Transitions = syn::parse2(quote! {
+            Transitions {
+                Advance [
+                    Green => Orange,
+                    Green => Red,
+                    Orange => Red,
+                ],
+                Poke [ Orange ~> Orange ]
+            }
+        })
+        .unwrap();
+        let context: Type = parse_quote!(Ctx);
+        let settings = Settings {
+            output: Some(parse_quote!(u8)),
+            ..Settings::default()
+        };
+
+        assert!(check(&states, false).is_ok());
+        assert!(check(&states, true).is_err());
+
+        let left = quote! {
+            /// State machine in state `S`. Only the transitions declared for `S`
+            /// exist as methods, so illegal sequences do not compile.
+            pub struct Machine<S> {
+                context: Ctx,
+                state: S,
+            }
+
+            impl Machine<Green> {
+                pub fn new() -> Machine<Green> {
+                    Machine {
+                        context: Ctx::default(),
+                        state: Default::default(),
+                    }
+                }
+            }
+
+            impl<S> Machine<S> {
+                pub fn context(&self) -> &Ctx {
+                    &self.context
+                }
+
+                pub fn state(&self) -> &S {
+                    &self.state
+                }
+
+                pub fn into_parts(self) -> (Ctx, S) {
+                    (self.context, self.state)
+                }
+            }
+
+            #[doc = "Targets `Green` can move to on `Advance`."]
+            pub enum GreenAdvanceTarget {
+                Orange(Orange),
+                Red(Red)
+            }
+
+            #[doc = "`Machine` after `Green` handled `Advance`."]
+            pub enum GreenAdvance {
+                Orange(Machine<Orange>),
+                Red(Machine<Red>)
+            }
+
+            impl Machine<Green> {
+                pub fn advance(mut self, event: Advance) -> GreenAdvance {
+                    let target = self.state.on_advance(&mut self.context, event);
+                    let context = self.context;
+                    match target {
+                        GreenAdvanceTarget::Orange(state) => GreenAdvance::Orange(Machine { context, state }),
+                        GreenAdvanceTarget::Red(state) => GreenAdvance::Red(Machine { context, state }),
+                    }
+                }
+            }
+
+            impl Machine<Orange> {
+                pub fn advance(mut self, event: Advance) -> Machine<Red> {
+                    let state = self.state.on_advance(&mut self.context, event);
+                    Machine {
+                        context: self.context,
+                        state,
+                    }
+                }
+
+                pub fn poke(&mut self, event: &Poke) {
+                    self.state.on_poke(event);
+                }
+            }
+
+            impl Machine<Red> {
+                /// Consume the terminated machine and convert its final
+                /// state into the output.
+                pub fn into_output(self) -> u8 {
+                    self.state.into()
+                }
+            }
+        };
+
+        let right = to_tokens(&context, &states, &finals, &events, &transitions, &settings);
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+    }
+}
@@ -0,0 +1,78 @@
+//! This is a support crate that contains the function-like procedural macros
+//! of the [fsm-rs] crate. All documentation lives in that crate.
+//!
+//! [fsm-rs]: https://docs.rs/fsm-rs
+
+// quote! macro needs a higher recursion limit
+#![recursion_limit = "512"]
+#![forbid(
+    future_incompatible,
+    macro_use_extern_crate,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    nonstandard_style,
+    rust_2018_compatibility,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    variant_size_differences
+)]
+#![warn(
+    missing_docs,
+    rust_2018_idioms,
+    single_use_lifetimes,
+    unused_import_braces,
+    unused_lifetimes,
+    unused_qualifications,
+    unused_results,
+    unused
+)]
+#![deny(clippy::all)]
+
+use crate::compat::{machine::MachineEnum, methods::MethodsDecl, transitions::TransitionTable};
+use crate::fsm::machine::Machine;
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse_macro_input;
+
+mod compat;
+mod fsm;
+
+/// Generate the declaratively described state machine diagram.
+///
+/// With `Mode = Typestate;` the `on_<event>` handlers are the only user code
+/// called: there are no `on()` guards and no `entry()`/`exit()` hooks.
+///
+/// See the main crate documentation for more details.
+#[proc_macro]
+pub fn fsm(input: TokenStream) -> TokenStream {
+    let machines: Machine = parse_macro_input!(input as Machine);
+
+    quote!(#machines).into()
+}
+
+/// Generate a `machine`-crate style state enum, one struct per state and
+/// snake_case constructors, including `error()`.
+#[proc_macro]
+pub fn machine(input: TokenStream) -> TokenStream {
+    let machine: MachineEnum = parse_macro_input!(input as MachineEnum);
+
+    quote!(#machine).into()
+}
+
+/// Generate the `on_<event>` methods of a `machine!` enum from
+/// `(State, Event) => Target` rows.
+#[proc_macro]
+pub fn transitions(input: TokenStream) -> TokenStream {
+    let transitions: TransitionTable = parse_macro_input!(input as TransitionTable);
+
+    quote!(#transitions).into()
+}
+
+/// Generate getters, setters and delegating methods on a `machine!` enum.
+#[proc_macro]
+pub fn methods(input: TokenStream) -> TokenStream {
+    let methods: MethodsDecl = parse_macro_input!(input as MethodsDecl);
+
+    quote!(#methods).into()
+}
@@ -0,0 +1,42 @@
+/// Snapshot and restore of a machine context, used by machines declared
+/// with `Transactional;` to undo a failed `event()` call.
+///
+/// Every `Clone` type implements it by cloning, and that impl cannot be
+/// replaced. Contexts that are not `Clone` implement it by hand, for example
+/// to snapshot only the fields an event can change.
+pub trait Checkpoint {
+    /// What `checkpoint()` saves.
+    type Snapshot;
+
+    /// Save the current value.
+    fn checkpoint(&self) -> Self::Snapshot;
+
+    /// Go back to the value saved by `checkpoint()`.
+    fn restore(&mut self, snapshot: Self::Snapshot);
+}
+
+impl<T: Clone> Checkpoint for T {
+    type Snapshot = T;
+
+    fn checkpoint(&self) -> T {
+        self.clone()
+    }
+
+    fn restore(&mut self, snapshot: T) {
+        *self = snapshot;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_clone() {
+        let mut v = [1, 2];
+        let snapshot = v.checkpoint();
+        v[1] = 3;
+        v.restore(snapshot);
+        assert_eq!(v, [1, 2]);
+    }
+}
@@ -0,0 +1,15 @@
+/// A default value usable in `const` contexts, required from the context
+/// and the initial state of machines declared with `Const;`, whose
+/// `Machine::new` is then a `const fn`:
+///
+/// ```text
+/// static MACHINE: Mutex<Machine> = Mutex::new(Machine::new());
+/// ```
+pub trait ConstDefault {
+    /// The default value.
+    const DEFAULT: Self;
+}
+
+impl ConstDefault for () {
+    const DEFAULT: Self = ();
+}
@@ -0,0 +1,558 @@
+//! Machines defined at runtime, for workflows that are configured rather than
+//! written in source.
+//!
+//! ```
+//! use fsm_rs::dynamic::MachineBuilder;
+//!
+//! let mut door = MachineBuilder::new()
+//!     .state("Closed")
+//!     .state("Open")
+//!     .event("open")
+//!     .event("close")
+//!     .transition("Closed", "open", "Open")
+//!     .guard(|unlocked: &bool| *unlocked)
+//!     .transition("Open", "close", "Closed")
+//!     .build(true)
+//!     .unwrap();
+//!
+//! assert_eq!(door.fire("open"), Ok(true));
+//! assert_eq!(door.state(), "Open");
+//! assert_eq!(door.fire("open"), Ok(false));
+//! ```
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+type Guard<C> = Box<dyn Fn(&C) -> bool>;
+type Action<C> = Box<dyn FnMut(&mut C)>;
+
+/// Graph checks run by [`MachineBuilder::build`]. They are the lints of
+/// `fsm!`, and like there every one of them is denied by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lint {
+    /// States that cannot be reached from the initial state.
+    UnreachableStates,
+    /// Declared events that no transition uses.
+    UnusedEvents,
+    /// Non-final states without any outgoing transition.
+    DeadEndStates,
+}
+
+/// Why [`MachineBuilder::build`] rejected a definition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// No state was declared, so there is no initial state.
+    NoStates,
+    /// The state was declared more than once.
+    DuplicateState(String),
+    /// The event was declared more than once.
+    DuplicateEvent(String),
+    /// A transition uses a state that was not declared.
+    UndeclaredState(String),
+    /// A transition uses an event that was not declared.
+    UndeclaredEvent(String),
+    /// `guard()` or `action()` was called before any `transition()`.
+    DetachedCallback,
+    /// The final state has outgoing transitions.
+    FinalWithTransitions(String),
+    /// See [`Lint::UnreachableStates`].
+    UnreachableState(String),
+    /// See [`Lint::UnusedEvents`].
+    UnusedEvent(String),
+    /// See [`Lint::DeadEndStates`].
+    DeadEndState(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::NoStates => {
+                f.write_str("a machine must declare at least the initial state")
+            }
+            BuildError::DuplicateState(name) => write!(f, "duplicate state `{}`", name),
+            BuildError::DuplicateEvent(name) => write!(f, "duplicate event `{}`", name),
+            BuildError::UndeclaredState(name) => write!(f, "state `{}` is not declared", name),
+            BuildError::UndeclaredEvent(name) => write!(f, "event `{}` is not declared", name),
+            BuildError::DetachedCallback => {
+                f.write_str("guard or action declared before any transition")
+            }
+            BuildError::FinalWithTransitions(name) => {
+                write!(f, "final state `{}` cannot have outgoing transitions", name)
+            }
+            BuildError::UnreachableState(name) => {
+                write!(f, "state `{}` is unreachable from the initial state", name)
+            }
+            BuildError::UnusedEvent(name) => {
+                write!(f, "event `{}` is not used by any transition", name)
+            }
+            BuildError::DeadEndState(name) => {
+                write!(f, "state `{}` has no outgoing transitions", name)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuildError {}
+
+/// Why [`Machine::fire`] refused an event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FireError {
+    /// The machine reached a final state and accepts no more events.
+    Terminated,
+    /// The event was never declared.
+    UnknownEvent(String),
+    /// Transitions exist for the event, but none of their guards passed.
+    Rejected,
+}
+
+impl fmt::Display for FireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FireError::Terminated => f.write_str("machine is terminated"),
+            FireError::UnknownEvent(name) => write!(f, "unknown event `{}`", name),
+            FireError::Rejected => f.write_str("all guards rejected the event"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FireError {}
+
+struct TransitionDef<C> {
+    from: String,
+    event: String,
+    to: String,
+    guard: Option<Guard<C>>,
+    action: Option<Action<C>>,
+}
+
+/// Declares a [`Machine`] step by step. The first declared state is the
+/// initial one.
+///
+/// `guard()` and `action()` attach to the transition declared just before
+/// them. When several transitions leave a state on the same event, the first
+/// one in declaration order whose guard passes is taken.
+pub struct MachineBuilder<C> {
+    states: Vec<String>,
+    finals: Vec<String>,
+    events: Vec<String>,
+    transitions: Vec<TransitionDef<C>>,
+    allowed: Vec<Lint>,
+    detached: bool,
+}
+
+impl<C> fmt::Debug for MachineBuilder<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MachineBuilder")
+            .field("states", &self.states)
+            .field("finals", &self.finals)
+            .field("events", &self.events)
+            .field("transitions", &self.transitions.len())
+            .finish()
+    }
+}
+
+impl<C> Default for MachineBuilder<C> {
+    fn default() -> Self {
+        MachineBuilder {
+            states: Vec::new(),
+            finals: Vec::new(),
+            events: Vec::new(),
+            transitions: Vec::new(),
+            allowed: Vec::new(),
+            detached: false,
+        }
+    }
+}
+
+impl<C> MachineBuilder<C> {
+    /// An empty definition.
+    pub fn new() -> Self {
+        MachineBuilder::default()
+    }
+
+    /// Declare a state.
+    pub fn state(mut self, name: impl Into<String>) -> Self {
+        self.states.push(name.into());
+        self
+    }
+
+    /// Declare a state in which the machine terminates.
+    pub fn final_state(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.states.push(name.clone());
+        self.finals.push(name);
+        self
+    }
+
+    /// Declare an event.
+    pub fn event(mut self, name: impl Into<String>) -> Self {
+        self.events.push(name.into());
+        self
+    }
+
+    /// Declare a transition from `from` to `to` on `event`.
+    pub fn transition(
+        mut self,
+        from: impl Into<String>,
+        event: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        self.transitions.push(TransitionDef {
+            from: from.into(),
+            event: event.into(),
+            to: to.into(),
+            guard: None,
+            action: None,
+        });
+        self
+    }
+
+    /// Only take the last declared transition when `guard` passes.
+    pub fn guard(mut self, guard: impl Fn(&C) -> bool + 'static) -> Self {
+        match self.transitions.last_mut() {
+            Some(transition) => transition.guard = Some(Box::new(guard)),
+            None => self.detached = true,
+        }
+        self
+    }
+
+    /// Run `action` on the context whenever the last declared transition is
+    /// taken.
+    pub fn action(mut self, action: impl FnMut(&mut C) + 'static) -> Self {
+        match self.transitions.last_mut() {
+            Some(transition) => transition.action = Some(Box::new(action)),
+            None => self.detached = true,
+        }
+        self
+    }
+
+    /// Do not report violations of `lint`.
+    pub fn allow(mut self, lint: Lint) -> Self {
+        self.allowed.push(lint);
+        self
+    }
+
+    /// Validate the definition and start the machine in its initial state.
+    /// Every problem found is reported, not only the first one.
+    pub fn build(self, context: C) -> Result<Machine<C>, Vec<BuildError>> {
+        let mut errors = Vec::new();
+
+        if self.states.is_empty() {
+            errors.push(BuildError::NoStates);
+        }
+        if self.detached {
+            errors.push(BuildError::DetachedCallback);
+        }
+
+        for (i, name) in self.states.iter().enumerate() {
+            if self.states[..i].contains(name) {
+                errors.push(BuildError::DuplicateState(name.clone()));
+            }
+        }
+        for (i, name) in self.events.iter().enumerate() {
+            if self.events[..i].contains(name) {
+                errors.push(BuildError::DuplicateEvent(name.clone()));
+            }
+        }
+
+        let state_index = |name: &String| self.states.iter().position(|v| v == name);
+        let event_index = |name: &String| self.events.iter().position(|v| v == name);
+
+        for transition in &self.transitions {
+            for name in &[&transition.from, &transition.to] {
+                if state_index(name).is_none() {
+                    errors.push(BuildError::UndeclaredState((*name).clone()));
+                }
+            }
+            if event_index(&transition.event).is_none() {
+                errors.push(BuildError::UndeclaredEvent(transition.event.clone()));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let has_outgoing = |name: &String| self.transitions.iter().any(|v| v.from == *name);
+
+        for name in &self.finals {
+            if has_outgoing(name) {
+                errors.push(BuildError::FinalWithTransitions(name.clone()));
+            }
+        }
+
+        // breadth-first walk from the initial state
+        let mut reached = vec![false; self.states.len()];
+        let mut queue = VecDeque::new();
+        reached[0] = true;
+        queue.push_back(&self.states[0]);
+        while let Some(from) = queue.pop_front() {
+            for transition in self.transitions.iter().filter(|v| v.from == *from) {
+                let to = state_index(&transition.to).unwrap();
+                if !reached[to] {
+                    reached[to] = true;
+                    queue.push_back(&transition.to);
+                }
+            }
+        }
+
+        for (i, name) in self.states.iter().enumerate() {
+            if !reached[i] && !self.allowed.contains(&Lint::UnreachableStates) {
+                errors.push(BuildError::UnreachableState(name.clone()));
+            }
+            if !self.finals.contains(name)
+                && !has_outgoing(name)
+                && !self.allowed.contains(&Lint::DeadEndStates)
+            {
+                errors.push(BuildError::DeadEndState(name.clone()));
+            }
+        }
+
+        for name in &self.events {
+            if self.transitions.iter().all(|v| v.event != *name)
+                && !self.allowed.contains(&Lint::UnusedEvents)
+            {
+                errors.push(BuildError::UnusedEvent(name.clone()));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let finals = self
+            .states
+            .iter()
+            .map(|v| self.finals.contains(v))
+            .collect();
+
+        let mut table: Vec<Vec<Vec<Transition<C>>>> = self
+            .states
+            .iter()
+            .map(|_| self.events.iter().map(|_| Vec::new()).collect())
+            .collect();
+        for transition in self.transitions {
+            let from = self
+                .states
+                .iter()
+                .position(|v| *v == transition.from)
+                .unwrap();
+            let event = self
+                .events
+                .iter()
+                .position(|v| *v == transition.event)
+                .unwrap();
+            let to = self
+                .states
+                .iter()
+                .position(|v| *v == transition.to)
+                .unwrap();
+            table[from][event].push(Transition {
+                to,
+                guard: transition.guard,
+                action: transition.action,
+            });
+        }
+
+        Ok(Machine {
+            states: self.states,
+            events: self.events,
+            finals,
+            table,
+            context,
+            current: 0,
+        })
+    }
+}
+
+struct Transition<C> {
+    to: usize,
+    guard: Option<Guard<C>>,
+    action: Option<Action<C>>,
+}
+
+/// A machine built by [`MachineBuilder`].
+pub struct Machine<C> {
+    states: Vec<String>,
+    events: Vec<String>,
+    finals: Vec<bool>,
+    /// candidate transitions by `[state][event]`, in declaration order
+    table: Vec<Vec<Vec<Transition<C>>>>,
+    context: C,
+    current: usize,
+}
+
+impl<C: fmt::Debug> fmt::Debug for Machine<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Machine")
+            .field("state", &self.state())
+            .field("context", &self.context)
+            .finish()
+    }
+}
+
+impl<C> Machine<C> {
+    /// Fire `event`. Returns whether a transition was taken, `Ok(false)`
+    /// meaning the current state declares none for it.
+    pub fn fire(&mut self, event: &str) -> Result<bool, FireError> {
+        if self.is_terminated() {
+            return Err(FireError::Terminated);
+        }
+
+        let event = match self.events.iter().position(|v| v == event) {
+            Some(event) => event,
+            None => return Err(FireError::UnknownEvent(event.to_string())),
+        };
+
+        let candidates = &mut self.table[self.current][event];
+        if candidates.is_empty() {
+            return Ok(false);
+        }
+
+        let context = &mut self.context;
+        let transition = candidates
+            .iter_mut()
+            .find(|v| match &v.guard {
+                Some(guard) => guard(context),
+                None => true,
+            })
+            .ok_or(FireError::Rejected)?;
+
+        if let Some(action) = &mut transition.action {
+            action(context);
+        }
+        self.current = transition.to;
+
+        Ok(true)
+    }
+
+    /// Name of the current state.
+    pub fn state(&self) -> &str {
+        &self.states[self.current]
+    }
+
+    /// Whether the machine is in one of its final states.
+    pub fn is_terminated(&self) -> bool {
+        self.finals[self.current]
+    }
+
+    /// The context passed to [`MachineBuilder::build`].
+    pub fn context(&self) -> &C {
+        &self.context
+    }
+
+    /// Mutable access to the context, e.g. to change what guards see.
+    pub fn context_mut(&mut self) -> &mut C {
+        &mut self.context
+    }
+
+    /// Declared states, the initial one first.
+    pub fn states(&self) -> impl Iterator<Item = &str> {
+        self.states.iter().map(String::as_str)
+    }
+
+    /// Declared events.
+    pub fn events(&self) -> impl Iterator<Item = &str> {
+        self.events.iter().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn traffic_light() -> MachineBuilder<u32> {
+        MachineBuilder::new()
+            .state("Green")
+            .state("Orange")
+            .state("Red")
+            .final_state("Off")
+            .event("advance")
+            .event("shutdown")
+            .transition("Green", "advance", "Orange")
+            .transition("Orange", "advance", "Red")
+            .transition("Red", "advance", "Green")
+            .action(|cycles| *cycles += 1)
+            .transition("Red", "shutdown", "Off")
+            .guard(|cycles| *cycles >= 1)
+    }
+
+    #[test]
+    fn test_machine_fire() {
+        let mut machine = traffic_light().build(0).unwrap();
+
+        assert_eq!(machine.state(), "Green");
+        assert_eq!(machine.fire("shutdown"), Ok(false));
+        assert_eq!(machine.fire("advance"), Ok(true));
+        assert_eq!(machine.fire("advance"), Ok(true));
+        assert_eq!(machine.fire("shutdown"), Err(FireError::Rejected));
+        assert_eq!(machine.state(), "Red");
+
+        assert_eq!(machine.fire("advance"), Ok(true));
+        assert_eq!(*machine.context(), 1);
+        assert_eq!(
+            machine.fire("blink"),
+            Err(FireError::UnknownEvent("blink".to_string()))
+        );
+
+        assert_eq!(machine.fire("advance"), Ok(true));
+        assert_eq!(machine.fire("advance"), Ok(true));
+        assert_eq!(machine.fire("shutdown"), Ok(true));
+        assert!(machine.is_terminated());
+        assert_eq!(machine.fire("advance"), Err(FireError::Terminated));
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let errors = MachineBuilder::<()>::new().build(()).unwrap_err();
+        assert_eq!(errors, vec![BuildError::NoStates]);
+
+        let errors = MachineBuilder::new()
+            .guard(|_: &()| true)
+            .state("A")
+            .state("A")
+            .event("e")
+            .transition("A", "f", "B")
+            .build(())
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                BuildError::DetachedCallback,
+                BuildError::DuplicateState("A".to_string()),
+                BuildError::UndeclaredState("B".to_string()),
+                BuildError::UndeclaredEvent("f".to_string()),
+            ]
+        );
+
+        let errors = traffic_light()
+            .state("Blink")
+            .event("toggle")
+            .transition("Off", "toggle", "Green")
+            .build(0)
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                BuildError::FinalWithTransitions("Off".to_string()),
+                BuildError::UnreachableState("Blink".to_string()),
+                BuildError::DeadEndState("Blink".to_string()),
+            ]
+        );
+
+        assert!(traffic_light()
+            .state("Blink")
+            .event("toggle")
+            .allow(Lint::UnreachableStates)
+            .allow(Lint::DeadEndStates)
+            .allow(Lint::UnusedEvents)
+            .build(0)
+            .is_ok());
+    }
+}
@@ -0,0 +1,21 @@
+/// Return types accepted from a state's `entry()`: `()` when entering the
+/// state cannot fail, or `Result<(), &'static str>` when it can.
+///
+/// A failed entry is routed to the state's `on_error` target if the machine
+/// declares one, and rejects the event otherwise.
+pub trait EntryResult {
+    /// The outcome of the entry.
+    fn into_result(self) -> Result<(), &'static str>;
+}
+
+impl EntryResult for () {
+    fn into_result(self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+impl EntryResult for Result<(), &'static str> {
+    fn into_result(self) -> Result<(), &'static str> {
+        self
+    }
+}
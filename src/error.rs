@@ -1,26 +1,83 @@
 use core::fmt::{Debug, Display, Formatter, Result};
 
-#[derive(Clone)]
+/// An error carrying a message, for callbacks and code generic over
+/// machines that need a single error type. The message is a `&'static str`
+/// like the ones returned by guards and callbacks, so no allocator is needed.
+#[derive(Clone, Copy)]
 pub struct Error {
-    message: String,
+    message: &'static str,
 }
 
 impl Error {
-    pub fn new<T: Display>(message: T) -> Self {
-        Error {
-            message: message.to_string(),
-        }
+    /// An error displaying `message`.
+    pub fn new(message: &'static str) -> Self {
+        Error { message }
+    }
+
+    /// The message the error was created with.
+    pub fn message(&self) -> &'static str {
+        self.message
     }
 }
 
 impl Debug for Error {
-    fn fmt(&self, formatter: &mut Formatter) -> Result {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
         formatter.debug_tuple("Error").field(&self.message).finish()
     }
 }
 
 impl Display for Error {
-    fn fmt(&self, formatter: &mut Formatter) -> Result {
-        formatter.write_str(&self.message)
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+        formatter.write_str(self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+impl From<TransitionError> for Error {
+    fn from(error: TransitionError) -> Self {
+        match error {
+            TransitionError::Terminated => Error::new("machine is terminated"),
+            TransitionError::Rejected(message) => Error::new(message),
+        }
+    }
+}
+
+/// Why a machine refused an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionError {
+    /// The machine reached a final state and accepts no more events.
+    Terminated,
+    /// The event guard, the state's `exit()` or its `entry()` refused the
+    /// event.
+    Rejected(&'static str),
+}
+
+impl Display for TransitionError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+        match self {
+            TransitionError::Terminated => formatter.write_str("machine is terminated"),
+            TransitionError::Rejected(err) => formatter.write_str(err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TransitionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{format, string::ToString};
+
+    #[test]
+    fn test_error_from_transition_error() {
+        let error: Error = TransitionError::Rejected("door is locked").into();
+        assert_eq!(error.to_string(), "door is locked");
+
+        let error: Error = TransitionError::Terminated.into();
+        assert_eq!(format!("{:?}", error), "Error(\"machine is terminated\")");
+        assert_eq!(error.message(), "machine is terminated");
     }
 }
@@ -1,5 +0,0 @@
-pub mod events;
-pub mod machine;
-pub mod machine_context;
-pub mod states;
-pub mod transitions;
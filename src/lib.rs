@@ -1,11 +1,16 @@
-//! This is a support crate that contains the function-like procedural macro to
-//! build state machines using the [sm] crate. All documentation lives in that
-//! crate.
+//! Finite state machines, declared at compile time with the [`fsm!`] macro or
+//! at runtime with [`dynamic::MachineBuilder`].
 //!
-//! [sm]: https://docs.rs/sm
+//! The `machine!`, `transitions!` and `methods!` macros provide a front-end
+//! compatible with the [machine] crate.
+//!
+//! [machine]: https://docs.rs/machine
+//!
+//! The generated code only uses `core`, so machines can be declared in
+//! `no_std` crates. Without the default `std` feature this crate is `no_std`
+//! too, and [`dynamic`] is only available with the `alloc` feature.
 
-// quote! macro needs a higher recursion limit
-#![recursion_limit = "512"]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(
     future_incompatible,
     macro_use_extern_crate,
@@ -20,7 +25,6 @@
 )]
 #![warn(
     missing_docs,
-    non_snake_case,
     rust_2018_idioms,
     single_use_lifetimes,
     unused_import_braces,
@@ -31,22 +35,35 @@
 )]
 #![deny(clippy::all)]
 
-extern crate proc_macro;
+pub use crate::checkpoint::Checkpoint;
+pub use crate::const_default::ConstDefault;
+pub use crate::entry::EntryResult;
+pub use crate::error::{Error, TransitionError};
+pub use crate::machine::{Id, StateMachine};
+pub use crate::observer::Observer;
+pub use crate::preview::{Callback, Preview};
+pub use fsm_rs_macros::{fsm, machine, methods, transitions};
 
-use crate::fsm::machine::Machine;
-use proc_macro::TokenStream;
-use quote::quote;
-use syn::parse_macro_input;
+// the `tracing` instrumentation of generated machines goes through this path,
+// so crates using them do not need their own `tracing` dependency
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing as __tracing;
 
-mod error;
-mod fsm;
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
-/// Generate the declaratively described state machine diagram.
-///
-/// See the main crate documentation for more details.
-#[proc_macro]
-pub fn fsm(input: TokenStream) -> TokenStream {
-    let machines: Machine = parse_macro_input!(input as Machine);
+// the unit tests format errors, which needs `std` even when the crate is not
+// built with it
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
 
-    quote!(#machines).into()
-}
+mod checkpoint;
+mod const_default;
+#[cfg(feature = "alloc")]
+pub mod dynamic;
+mod entry;
+mod error;
+mod machine;
+mod observer;
+mod preview;
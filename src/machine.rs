@@ -0,0 +1,46 @@
+use core::fmt::{Debug, Display};
+use core::hash::Hash;
+use core::str::FromStr;
+
+/// Implemented by every `Machine` generated by `fsm!` in the default
+/// `Mode = Match;`, so infrastructure such as persistence or supervision can
+/// be written once for all of them.
+pub trait StateMachine {
+    /// The generated `State` enum.
+    type State;
+    /// The generated `Event` enum.
+    type Event;
+    /// The `Context = ...;` type.
+    type Context;
+    /// Why `handle` refused an event.
+    type Error;
+
+    /// The current state.
+    fn state(&self) -> &Self::State;
+
+    /// Dispatch `event`. Returns whether a transition was taken.
+    fn handle(&mut self, event: Self::Event) -> Result<bool, Self::Error>;
+
+    /// Whether a transition is declared for `event` in the current state.
+    /// Guards are not evaluated.
+    fn can_handle(&self, event: &Self::Event) -> bool;
+
+    /// The context the machine was created with.
+    fn context(&self) -> &Self::Context;
+
+    /// A human readable summary of the states, events and transitions.
+    fn description(&self) -> &'static str;
+
+    /// Whether the machine is in one of its final states.
+    fn is_terminated(&self) -> bool;
+}
+
+/// Implemented by the fieldless `StateId` and `EventId` enums generated by
+/// `fsm!`.
+pub trait Id: Copy + Debug + Display + Eq + Hash + Ord + FromStr + Send + Sync + 'static {
+    /// Every variant, in declaration order.
+    fn all() -> &'static [Self];
+
+    /// The declared name of the variant.
+    fn name(self) -> &'static str;
+}
@@ -0,0 +1,27 @@
+use crate::TransitionError;
+
+/// Hooks invoked by a machine while it dispatches events, generic over its
+/// state type `S` and event type `E`.
+///
+/// Every method has an empty default body, so implementors only override
+/// what they need. `()` is the no-op observer used by `Machine::new()` and
+/// costs nothing at runtime. Implementing it for any `S` and `E` gives one
+/// observer, e.g. a metrics exporter, usable with every machine.
+pub trait Observer<S, E> {
+    /// Called for every event before it is dispatched.
+    fn on_event_received(&mut self, _state: &S, _event: &E) {}
+
+    /// Called after `event` moved the machine from `from` to `to`.
+    /// Internal transitions report the same state twice.
+    fn on_transition(&mut self, _from: &S, _to: &S, _event: &E) {}
+
+    /// Called when the event was refused, either because the machine is
+    /// terminated, by the event guard, the state's `exit()` or a failed
+    /// `entry()` without `on_error` route.
+    fn on_rejected(&mut self, _state: &S, _event: &E, _error: &TransitionError) {}
+
+    /// Called when no transition is declared for `event` in `state`.
+    fn on_unhandled(&mut self, _state: &S, _event: &E) {}
+}
+
+impl<S, E> Observer<S, E> for () {}
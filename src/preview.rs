@@ -0,0 +1,74 @@
+/// A user callback `Machine::event` calls, named by the `StateId` /
+/// `EventId` of the type it is implemented on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Callback<S, E> {
+    /// The event guard, `on()`.
+    Guard(E),
+    /// `exit()` of the state being left, which picks the target of
+    /// transitions with a single one unless the machine uses
+    /// `Dispatch = Handler;`. Otherwise only its error is used, so a state
+    /// whose targets are all picked by handlers may return
+    /// `Result<(), &'static str>`.
+    Exit(S),
+    /// `entry()` of the state being entered.
+    Entry(S),
+    /// `on_<event>()` of the state handling the event: the action of an
+    /// internal transition, or the handler picking the target of a
+    /// transition with several, or of any with `Dispatch = Handler;`.
+    Action(S, E),
+}
+
+/// What `Machine::event` would do with an event, returned by
+/// `Machine::preview` without running any callback but the guard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Preview<S: 'static, E: 'static> {
+    /// The machine is terminated and refuses every event.
+    pub terminated: bool,
+    /// Result of the event guard, `None` when no transition uses the event.
+    pub guard: Option<Result<(), &'static str>>,
+    /// The transition stays in the current state, skipping exit and entry.
+    pub internal: bool,
+    /// States the current state may move to, empty when it does not handle
+    /// the event.
+    pub targets: &'static [S],
+    /// Callbacks `event` would call, in order. When there are several
+    /// targets, only the `Entry` of the chosen one runs.
+    pub callbacks: &'static [Callback<S, E>],
+}
+
+impl<S, E> Preview<S, E> {
+    /// Whether the event would be handled, provided `exit()` does not
+    /// refuse it.
+    pub fn is_accepted(&self) -> bool {
+        !self.terminated && self.guard == Some(Ok(())) && !self.targets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_is_accepted() {
+        let preview: Preview<u8, u8> = Preview {
+            terminated: false,
+            guard: Some(Ok(())),
+            internal: false,
+            targets: &[2],
+            callbacks: &[Callback::Guard(1), Callback::Exit(1), Callback::Entry(2)],
+        };
+        assert!(preview.is_accepted());
+
+        assert!(!Preview {
+            guard: Some(Err("locked")),
+            callbacks: &preview.callbacks[..1],
+            ..preview
+        }
+        .is_accepted());
+        assert!(!Preview {
+            targets: &[],
+            ..preview
+        }
+        .is_accepted());
+    }
+}
@@ -0,0 +1,200 @@
+//! `Backend = Table;` behaves like `Backend = Match;`: the same events give
+//! the same results, states, context and observer calls.
+
+/// Expand the machine in a module, passing the given settings to `fsm!`.
+macro_rules! turnstile {
+    ($module:ident, $($settings:tt)*) => {
+        mod $module {
+            use fsm_rs::{fsm, Observer, TransitionError};
+
+            #[derive(Clone, Debug, Default, PartialEq)]
+            pub struct Ctx {
+                pub stops: u32,
+            }
+
+            #[derive(Clone, Debug, Default, PartialEq)]
+            pub struct Idle;
+
+            impl Idle {
+                fn exit(&mut self) -> Result<State, &'static str> {
+                    Ok(State::Running(Running { count: 0 }))
+                }
+
+                fn entry(&mut self) {}
+            }
+
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct Running {
+                pub count: u32,
+            }
+
+            impl Running {
+                fn on_tick(&mut self, tick: &Tick) {
+                    self.count += tick.0;
+                }
+
+                fn on_stop(&mut self, ctx: &mut Ctx, _stop: Stop) -> RunningStopTarget {
+                    ctx.stops += 1;
+                    if self.count > 3 {
+                        RunningStopTarget::Jammed(Jammed)
+                    } else {
+                        RunningStopTarget::Idle(Idle)
+                    }
+                }
+
+                fn exit(&mut self) -> Result<State, &'static str> {
+                    Ok(State::Idle(Idle))
+                }
+
+                fn entry(&mut self) {}
+            }
+
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct Jammed;
+
+            impl Jammed {
+                fn exit(&mut self) -> Result<State, &'static str> {
+                    Err("jammed")
+                }
+
+                fn entry(&mut self) {}
+            }
+
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct Tick(pub u32);
+
+            impl Tick {
+                fn on(&self) -> Result<(), &'static str> {
+                    if self.0 == 0 {
+                        Err("empty tick")
+                    } else {
+                        Ok(())
+                    }
+                }
+            }
+
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct Stop;
+
+            impl Stop {
+                fn on(&self) -> Result<(), &'static str> {
+                    Ok(())
+                }
+            }
+
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct Reset;
+
+            impl Reset {
+                fn on(&self) -> Result<(), &'static str> {
+                    Ok(())
+                }
+            }
+
+            fsm! {
+                Context = Ctx;
+
+                $($settings)*
+
+                States {
+                    Idle = Idle,
+                    Running = Running,
+                    Jammed = Jammed
+                }
+
+                Events {
+                    Tick = Tick,
+                    Stop = Stop,
+                    Reset = Reset
+                }
+
+                Transitions {
+                    Tick [ Idle => Running, Running ~> Running ],
+                    Stop [ Running => Idle, Running => Jammed ],
+                    Reset [ Jammed => Idle ]
+                }
+            }
+
+            /// Every observer call, formatted.
+            #[derive(Default)]
+            pub struct Log(pub Vec<String>);
+
+            impl Observer<State, Event> for Log {
+                fn on_event_received(&mut self, state: &State, event: &Event) {
+                    self.0.push(format!("received {:?} in {:?}", event, state));
+                }
+
+                fn on_transition(&mut self, from: &State, to: &State, event: &Event) {
+                    self.0.push(format!("{:?} -> {:?} on {:?}", from, to, event));
+                }
+
+                fn on_rejected(&mut self, state: &State, event: &Event, error: &TransitionError) {
+                    self.0.push(format!("rejected {:?} in {:?}: {:?}", event, state, error));
+                }
+
+                fn on_unhandled(&mut self, state: &State, event: &Event) {
+                    self.0.push(format!("unhandled {:?} in {:?}", event, state));
+                }
+            }
+
+            /// Run the shared script, returning each result and state, the
+            /// final context and the observer calls.
+            pub fn run() -> (Vec<String>, Ctx, Vec<String>) {
+                let events = vec![
+                    Event::Reset(Reset),
+                    Event::Tick(Tick(0)),
+                    Event::Tick(Tick(1)),
+                    Event::Tick(Tick(2)),
+                    Event::Stop(Stop),
+                    Event::Tick(Tick(5)),
+                    Event::Tick(Tick(5)),
+                    Event::Stop(Stop),
+                    Event::Reset(Reset),
+                    Event::Tick(Tick(1)),
+                ];
+
+                let mut machine = Machine::with_observer(Log::default());
+                let steps = events
+                    .into_iter()
+                    .map(|event| {
+                        let result = machine.event(event);
+                        format!("{:?} {:?}", result, machine.state())
+                    })
+                    .collect();
+
+                (steps, machine.context().clone(), machine.observer().0.clone())
+            }
+        }
+    };
+}
+
+turnstile!(by_match, Backend = Match;);
+turnstile!(by_table, Backend = Table;);
+
+#[test]
+fn test_backends_agree() {
+    let (steps, ctx, log) = by_match::run();
+
+    assert_eq!(
+        steps,
+        vec![
+            "Ok(false) Idle(Idle)",
+            "Err(Rejected(\"empty tick\")) Idle(Idle)",
+            "Ok(true) Running(Running { count: 0 })",
+            "Ok(true) Running(Running { count: 2 })",
+            "Ok(true) Idle(Idle)",
+            "Ok(true) Running(Running { count: 0 })",
+            "Ok(true) Running(Running { count: 5 })",
+            "Ok(true) Jammed(Jammed)",
+            "Err(Rejected(\"jammed\")) Jammed(Jammed)",
+            "Ok(false) Jammed(Jammed)",
+        ]
+    );
+    assert_eq!(ctx.stops, 2);
+    assert_eq!(log.len(), 20);
+
+    let (table_steps, table_ctx, table_log) = by_table::run();
+    assert_eq!(steps, table_steps);
+    assert_eq!(ctx.stops, table_ctx.stops);
+    assert_eq!(log, table_log);
+}
@@ -0,0 +1,117 @@
+//! With `Dispatch = Handler;` the `on_<event>` handler builds the target
+//! state. `exit()` runs first and can refuse the transition, in which case
+//! the handler is not called and `event()` fails.
+
+use fsm_rs::{fsm, TransitionError};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bank {
+    pub balance: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Open {
+    pub withdrawals: u32,
+    pub exits: u32,
+}
+
+impl Open {
+    fn on_withdraw(&mut self, bank: &mut Bank, withdraw: Withdraw) -> Open {
+        bank.balance -= withdraw.0;
+        Open {
+            withdrawals: self.withdrawals + 1,
+            exits: self.exits,
+        }
+    }
+
+    fn on_lock(&mut self, _bank: &mut Bank, _lock: Lock) -> Frozen {
+        Frozen { exits: self.exits }
+    }
+
+    fn exit(&mut self) -> Result<(), &'static str> {
+        if self.withdrawals >= 2 {
+            return Err("daily limit");
+        }
+        self.exits += 1;
+        Ok(())
+    }
+
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frozen {
+    pub exits: u32,
+}
+
+impl Frozen {
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Withdraw(i32);
+
+impl Withdraw {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lock;
+
+impl Lock {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+fsm! {
+    Context = Bank;
+
+    Dispatch = Handler;
+
+    States {
+        Open = Open,
+        Frozen = Frozen
+    }
+
+    Final { Frozen }
+
+    Events {
+        Withdraw = Withdraw,
+        Lock = Lock
+    }
+
+    Transitions {
+        Withdraw [ Open => Open ],
+        Lock [ Open => Frozen ]
+    }
+}
+
+#[test]
+fn test_handler_dispatch() {
+    let mut machine = Machine::new();
+    assert_eq!(machine.event(Event::Withdraw(Withdraw(10))), Ok(true));
+    assert_eq!(machine.event(Event::Withdraw(Withdraw(5))), Ok(true));
+    assert_eq!(
+        machine.state(),
+        &State::Open(Open {
+            withdrawals: 2,
+            exits: 2,
+        })
+    );
+    assert_eq!(machine.context(), &Bank { balance: -15 });
+
+    assert_eq!(
+        machine.event(Event::Withdraw(Withdraw(7))),
+        Err(TransitionError::Rejected("daily limit"))
+    );
+    assert_eq!(machine.context(), &Bank { balance: -15 });
+
+    let mut machine = Machine::new();
+    assert_eq!(machine.event(Event::Withdraw(Withdraw(1))), Ok(true));
+    assert_eq!(machine.event(Event::Lock(Lock)), Ok(true));
+    assert_eq!(machine.state(), &State::Frozen(Frozen { exits: 2 }));
+    assert!(machine.is_terminated());
+}
@@ -1,5 +1,5 @@
 #[macro_use]
-extern crate machine;
+extern crate fsm_rs;
 
 pub trait Transitions {
     fn next(&mut self);
@@ -1,5 +1,8 @@
+// the parser states keep the request line around for later stages
+#![allow(dead_code)]
+
 #[macro_use]
-extern crate machine;
+extern crate fsm_rs;
 
 machine!(
     enum HttpRequest {
@@ -152,3 +155,24 @@ impl HasHostAndLength {
         }
     }
 }
+
+#[test]
+fn test() {
+    let mut request = HttpRequest::initial();
+    assert_eq!(request.host(), None);
+
+    request = request.on_request_line(RequestLine);
+    request = request.on_host_header(HostHeader("example.com".to_string()));
+    assert_eq!(request.host(), Some("example.com"));
+
+    request = request.on_length_header(LengthHeader(LengthInfo::Length(42)));
+    request = request.on_header_end(HeaderEnd);
+    match &request {
+        HttpRequest::RequestWithBody(state) => assert_eq!(state.remaining, 42),
+        _ => panic!("expected a request with body"),
+    }
+    assert_eq!(request.host(), Some("example.com"));
+
+    request = request.on_header_end(HeaderEnd);
+    assert!(request.is_error());
+}
@@ -0,0 +1,125 @@
+//! `~>` runs the state's `on_<event>` action and stays in the state without
+//! calling `exit()` or `entry()`.
+
+use fsm_rs::{fsm, Observer};
+
+#[derive(Default)]
+pub struct Counter;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Idle {
+    pub pokes: u32,
+    pub exits: u32,
+    pub entries: u32,
+}
+
+impl Idle {
+    fn on_poke(&mut self, _poke: &Poke) {
+        self.pokes += 1;
+    }
+
+    fn exit(&mut self) -> Result<State, &'static str> {
+        self.exits += 1;
+        Ok(State::Busy(Busy))
+    }
+
+    fn entry(&mut self) {
+        self.entries += 1;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Busy;
+
+impl Busy {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Idle(Idle::default()))
+    }
+
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Poke;
+
+impl Poke {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Go;
+
+impl Go {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+fsm! {
+    Context = Counter;
+
+    States {
+        Idle = Idle,
+        Busy = Busy
+    }
+
+    Events {
+        Poke = Poke,
+        Go = Go
+    }
+
+    Transitions {
+        Poke [ Idle ~> Idle ],
+        Go [ Idle => Busy, Busy => Idle ]
+    }
+}
+
+#[derive(Default)]
+struct Transitions(Vec<(StateId, StateId)>);
+
+impl Observer<State, Event> for Transitions {
+    fn on_transition(&mut self, from: &State, to: &State, _event: &Event) {
+        self.0.push((from.id(), to.id()));
+    }
+}
+
+#[test]
+fn test_internal_transition() {
+    let mut machine = Machine::with_observer(Transitions::default());
+
+    assert_eq!(machine.event(Event::Poke(Poke)), Ok(true));
+    assert_eq!(machine.event(Event::Poke(Poke)), Ok(true));
+    assert_eq!(
+        machine.state(),
+        &State::Idle(Idle {
+            pokes: 2,
+            exits: 0,
+            entries: 0,
+        })
+    );
+
+    assert_eq!(machine.event(Event::Go(Go)), Ok(true));
+    assert_eq!(machine.event(Event::Go(Go)), Ok(true));
+    assert_eq!(machine.event(Event::Poke(Poke)), Ok(true));
+    assert_eq!(
+        machine.state(),
+        &State::Idle(Idle {
+            pokes: 1,
+            exits: 0,
+            entries: 1,
+        })
+    );
+
+    assert_eq!(
+        machine.observer().0,
+        vec![
+            (StateId::Idle, StateId::Idle),
+            (StateId::Idle, StateId::Idle),
+            (StateId::Idle, StateId::Busy),
+            (StateId::Busy, StateId::Idle),
+            (StateId::Idle, StateId::Idle),
+        ]
+    );
+}
@@ -0,0 +1,113 @@
+//! The `Methods` section adds accessors and delegated methods to `State`,
+//! answered by the states listing them.
+
+use fsm_rs::fsm;
+
+#[derive(Default)]
+pub struct Player;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stopped;
+
+impl Stopped {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Playing(Playing { track: 1 }))
+    }
+
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Playing {
+    pub track: u8,
+}
+
+impl Playing {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Paused(Paused { track: self.track }))
+    }
+
+    fn entry(&mut self) {}
+
+    fn is_audible(&self) -> bool {
+        true
+    }
+
+    fn skip(&self, by: u8) -> u8 {
+        self.track + by
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Paused {
+    pub track: u8,
+}
+
+impl Paused {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Stopped(Stopped))
+    }
+
+    fn entry(&mut self) {}
+
+    fn is_audible(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Press;
+
+impl Press {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+fsm! {
+    Context = Player;
+
+    States {
+        Stopped = Stopped,
+        Playing = Playing,
+        Paused = Paused
+    }
+
+    Events {
+        Press = Press
+    }
+
+    Transitions {
+        Press [ Stopped => Playing, Playing => Paused, Paused => Stopped ]
+    }
+
+    Methods {
+        Playing, Paused => get track: u8,
+        Playing => set track: u8,
+        Playing, Paused => default(false) fn is_audible(&self) -> bool,
+        Playing => fn skip(&self, by: u8) -> u8
+    }
+}
+
+#[test]
+fn test_methods() {
+    let mut machine = Machine::new();
+    assert_eq!(machine.state().track(), None);
+    assert!(!machine.state().is_audible());
+    assert_eq!(machine.state().skip(2), None);
+
+    assert_eq!(machine.event(Event::Press(Press)), Ok(true));
+    assert_eq!(machine.state().track(), Some(&1));
+    assert!(machine.state().is_audible());
+    assert_eq!(machine.state().skip(2), Some(3));
+
+    assert_eq!(machine.event(Event::Press(Press)), Ok(true));
+    assert_eq!(machine.state().track(), Some(&1));
+    assert!(!machine.state().is_audible());
+    assert_eq!(machine.state().skip(2), None);
+
+    let mut state = State::Playing(Playing { track: 4 });
+    *state.track_mut().unwrap() = 5;
+    assert_eq!(state, State::Playing(Playing { track: 5 }));
+    assert_eq!(State::Paused(Paused { track: 4 }).track_mut(), None);
+}
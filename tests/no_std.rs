@@ -0,0 +1,130 @@
+//! Machines declared in a `no_std` crate: the generated code only needs
+//! `core`, and with `Const;` the machine is built at compile time. CI also
+//! runs it with `--no-default-features`, so `fsm-rs` is `no_std` as well.
+
+#![no_std]
+
+use fsm_rs::{fsm, ConstDefault, TransitionError};
+
+#[derive(Clone)]
+pub struct Board {
+    pub blinks: u32,
+}
+
+impl ConstDefault for Board {
+    const DEFAULT: Self = Board { blinks: 0 };
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Off;
+
+impl ConstDefault for Off {
+    const DEFAULT: Self = Off;
+}
+
+impl Off {
+    fn on_power(&self, board: &mut Board, power: Power) -> OffPowerTarget {
+        board.blinks += 1;
+        if power.0 {
+            OffPowerTarget::On(On)
+        } else {
+            OffPowerTarget::Fault(Fault("brownout"))
+        }
+    }
+
+    fn exit(&mut self) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct On;
+
+impl On {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Off(Off))
+    }
+
+    fn entry(&mut self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fault(&'static str);
+
+impl From<&'static str> for Fault {
+    fn from(error: &'static str) -> Self {
+        Fault(error)
+    }
+}
+
+impl Fault {
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Power(bool);
+
+impl Power {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toggle;
+
+impl Toggle {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+fsm! {
+    Context = Board;
+
+    History = 4;
+    Transactional;
+    ErrorState = Fault;
+    Const;
+
+    States {
+        Off = Off,
+        On = On,
+        Fault = Fault
+    }
+
+    Final { Fault }
+
+    Events {
+        Power = Power,
+        Toggle = Toggle
+    }
+
+    Transitions {
+        Power [ Off => On, Off => Fault ],
+        Toggle [ On => Off ],
+    }
+}
+
+const POWERED_OFF: Machine = Machine::new();
+
+#[test]
+fn test_no_std_machine() {
+    let mut machine = POWERED_OFF;
+    assert_eq!(machine.state(), &State::Off(Off));
+    assert_eq!(machine.event(Event::Power(Power(true))), Ok(true));
+    assert_eq!(machine.event(Event::Toggle(Toggle)), Ok(true));
+    assert_eq!(machine.event(Event::Power(Power(false))), Ok(true));
+    assert_eq!(machine.state(), &State::Fault(Fault("brownout")));
+    assert!(machine.is_terminated());
+    assert_eq!(machine.context().blinks, 2);
+    assert_eq!(machine.history().len(), 3);
+    assert_eq!(
+        machine.event(Event::Toggle(Toggle)),
+        Err(TransitionError::Terminated)
+    );
+}
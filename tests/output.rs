@@ -0,0 +1,80 @@
+//! `Output = T;` converts the final state of a terminated machine into `T`.
+
+use fsm_rs::fsm;
+
+#[derive(Default)]
+pub struct Review;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pending;
+
+impl Pending {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Approved(Approved { score: 7 }))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Approved {
+    score: u8,
+}
+
+impl Approved {
+    fn entry(&mut self) {}
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Verdict {
+    pub score: u8,
+}
+
+impl From<Approved> for Verdict {
+    fn from(approved: Approved) -> Self {
+        Verdict {
+            score: approved.score,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decide;
+
+impl Decide {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+fsm! {
+    Context = Review;
+
+    Output = Verdict;
+
+    States {
+        Pending = Pending,
+        Approved = Approved
+    }
+
+    Final { Approved }
+
+    Events {
+        Decide = Decide
+    }
+
+    Transitions {
+        Decide [ Pending => Approved ]
+    }
+}
+
+#[test]
+fn test_into_output() {
+    let mut machine = match Machine::new().into_output() {
+        Ok(_) => panic!("a pending review has no verdict"),
+        Err(machine) => machine,
+    };
+    assert_eq!(machine.state(), &State::Pending(Pending));
+
+    assert_eq!(machine.event(Event::Decide(Decide)), Ok(true));
+    assert!(machine.is_terminated());
+    assert_eq!(machine.into_output().ok(), Some(Verdict { score: 7 }));
+}
@@ -0,0 +1,148 @@
+//! `preview()` reports what `event()` would do, running only the guard: the
+//! state and the context are left as they were.
+
+use fsm_rs::{fsm, Callback, Preview};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Meter {
+    pub pings: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Idle {
+    pub exits: u32,
+}
+
+impl Idle {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        self.exits += 1;
+        Ok(State::Running(Running))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Running;
+
+impl Running {
+    fn on_ping(&mut self, _ping: &Ping) {}
+
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Done(Done))
+    }
+
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Done;
+
+impl Done {
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Start(u8);
+
+impl Start {
+    fn on(&self) -> Result<(), &'static str> {
+        if self.0 == 0 {
+            Err("no speed")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ping;
+
+impl Ping {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stop;
+
+impl Stop {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+fsm! {
+    Context = Meter;
+
+    States {
+        Idle = Idle,
+        Running = Running,
+        Done = Done
+    }
+
+    Final { Done }
+
+    Events {
+        Start = Start,
+        Ping = Ping,
+        Stop = Stop
+    }
+
+    Transitions {
+        Start [ Idle => Running ],
+        Ping [ Running ~> Running ],
+        Stop [ Running => Done ]
+    }
+}
+
+#[test]
+fn test_preview() {
+    let mut machine = Machine::new();
+
+    assert_eq!(
+        machine.preview(&Event::Start(Start(1))),
+        Preview {
+            terminated: false,
+            guard: Some(Ok(())),
+            internal: false,
+            targets: &[StateId::Running],
+            callbacks: &[
+                Callback::Guard(EventId::Start),
+                Callback::Exit(StateId::Idle),
+                Callback::Entry(StateId::Running),
+            ],
+        }
+    );
+
+    let refused = machine.preview(&Event::Start(Start(0)));
+    assert_eq!(refused.guard, Some(Err("no speed")));
+    assert_eq!(refused.callbacks, &[Callback::Guard(EventId::Start)]);
+    assert!(!refused.is_accepted());
+
+    let unhandled = machine.preview(&Event::Stop(Stop));
+    assert!(unhandled.targets.is_empty());
+    assert!(!unhandled.is_accepted());
+
+    // exit() was not called
+    assert_eq!(machine.state(), &State::Idle(Idle { exits: 0 }));
+    assert_eq!(machine.context(), &Meter::default());
+
+    assert_eq!(machine.event(Event::Start(Start(1))), Ok(true));
+    let internal = machine.preview(&Event::Ping(Ping));
+    assert!(internal.internal && internal.is_accepted());
+    assert_eq!(
+        internal.callbacks,
+        &[
+            Callback::Guard(EventId::Ping),
+            Callback::Action(StateId::Running, EventId::Ping),
+        ]
+    );
+    assert_eq!(
+        machine.preview(&Event::Stop(Stop)).targets,
+        &[StateId::Done]
+    );
+    assert_eq!(machine.state(), &State::Running(Running));
+
+    assert_eq!(machine.event(Event::Stop(Stop)), Ok(true));
+    assert!(machine.preview(&Event::Ping(Ping)).terminated);
+}
@@ -0,0 +1,120 @@
+//! `can_fire` and `permitted_events` follow the declared transitions of the
+//! current state, `can_fire_with` also runs the event's guard.
+
+use fsm_rs::fsm;
+
+#[derive(Default)]
+pub struct Door;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Closed;
+
+impl Closed {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Opened(Opened))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Opened;
+
+impl Opened {
+    fn on_pull(&mut self, _pull: &Pull) {}
+
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Broken(Broken))
+    }
+
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Broken;
+
+impl Broken {
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Push(u8);
+
+impl Push {
+    fn on(&self) -> Result<(), &'static str> {
+        if self.0 == 0 {
+            Err("too weak")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kick;
+
+impl Kick {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pull;
+
+impl Pull {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+fsm! {
+    Context = Door;
+
+    States {
+        Closed = Closed,
+        Opened = Opened,
+        Broken = Broken
+    }
+
+    Final { Broken }
+
+    Events {
+        Push = Push,
+        Kick = Kick,
+        Pull = Pull
+    }
+
+    Transitions {
+        Push [ Closed => Opened ],
+        Kick [ Opened => Broken ],
+        Pull [ Opened ~> Opened ]
+    }
+}
+
+#[test]
+fn test_queries() {
+    let mut machine = Machine::new();
+    assert!(machine.can_fire(EventId::Push));
+    assert!(!machine.can_fire(EventId::Kick));
+    assert!(!machine.can_fire(EventId::Pull));
+    assert_eq!(
+        machine.permitted_events().collect::<Vec<_>>(),
+        vec![EventId::Push]
+    );
+    assert!(machine.can_fire_with(&Event::Push(Push(1))));
+    assert!(!machine.can_fire_with(&Event::Push(Push(0))));
+    assert!(!machine.can_fire_with(&Event::Kick(Kick)));
+
+    assert_eq!(machine.event(Event::Push(Push(1))), Ok(true));
+    assert!(!machine.can_fire(EventId::Push));
+    assert_eq!(
+        machine.permitted_events().collect::<Vec<_>>(),
+        vec![EventId::Kick, EventId::Pull]
+    );
+    assert!(machine.can_fire_with(&Event::Pull(Pull)));
+
+    assert_eq!(machine.event(Event::Kick(Kick)), Ok(true));
+    assert!(machine.is_terminated());
+    assert_eq!(machine.permitted_events().count(), 0);
+    assert!(!machine.can_fire(EventId::Kick));
+    assert!(!machine.can_fire_with(&Event::Pull(Pull)));
+}
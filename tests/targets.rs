@@ -0,0 +1,159 @@
+//! Refused transitions leave the machine unchanged under both backends:
+//! `exit()` refuses before a handler picking among several targets touches
+//! the context, and an `exit()` returning a state the event does not move to
+//! is rejected.
+
+/// Expand the machine and its test in a module, passing the given settings
+/// to `fsm!`.
+macro_rules! vending {
+    ($module:ident, $($settings:tt)*) => {
+        mod $module {
+            use fsm_rs::{fsm, TransitionError};
+
+            #[derive(Clone, Debug, Default, PartialEq)]
+            pub struct Ctx {
+                pub coins: u32,
+            }
+
+            #[derive(Clone, Debug, Default, PartialEq)]
+            pub struct Idle {
+                pub locked: bool,
+            }
+
+            impl Idle {
+                fn on_lock(&mut self, _lock: &Lock) {
+                    self.locked = !self.locked;
+                }
+
+                fn on_insert(&mut self, ctx: &mut Ctx, insert: Insert) -> IdleInsertTarget {
+                    ctx.coins += insert.0;
+                    if insert.0 >= 2 {
+                        IdleInsertTarget::Vending(Vending { jam: insert.0 > 5 })
+                    } else {
+                        IdleInsertTarget::Refunding(Refunding)
+                    }
+                }
+
+                // every transition out of Idle is picked by a handler
+                fn exit(&mut self) -> Result<(), &'static str> {
+                    if self.locked {
+                        Err("locked")
+                    } else {
+                        Ok(())
+                    }
+                }
+
+                fn entry(&mut self) {}
+            }
+
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct Vending {
+                pub jam: bool,
+            }
+
+            impl Vending {
+                fn exit(&mut self) -> Result<State, &'static str> {
+                    if self.jam {
+                        Ok(State::Refunding(Refunding))
+                    } else {
+                        Ok(State::Idle(Idle::default()))
+                    }
+                }
+
+                fn entry(&mut self) {}
+            }
+
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct Refunding;
+
+            impl Refunding {
+                fn exit(&mut self) -> Result<State, &'static str> {
+                    Ok(State::Idle(Idle::default()))
+                }
+
+                fn entry(&mut self) {}
+            }
+
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct Lock;
+
+            impl Lock {
+                fn on(&self) -> Result<(), &'static str> {
+                    Ok(())
+                }
+            }
+
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct Insert(pub u32);
+
+            impl Insert {
+                fn on(&self) -> Result<(), &'static str> {
+                    Ok(())
+                }
+            }
+
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct Take;
+
+            impl Take {
+                fn on(&self) -> Result<(), &'static str> {
+                    Ok(())
+                }
+            }
+
+            fsm! {
+                Context = Ctx;
+
+                $($settings)*
+
+                States {
+                    Idle = Idle,
+                    Vending = Vending,
+                    Refunding = Refunding
+                }
+
+                Events {
+                    Lock = Lock,
+                    Insert = Insert,
+                    Take = Take
+                }
+
+                Transitions {
+                    Lock [ Idle ~> Idle ],
+                    Insert [ Idle => Vending, Idle => Refunding ],
+                    Take [ Vending => Idle, Refunding => Idle ]
+                }
+            }
+
+            #[test]
+            fn test_refused_transitions() {
+                let mut machine = Machine::new();
+                assert_eq!(machine.event(Event::Lock(Lock)), Ok(true));
+                assert_eq!(
+                    machine.event(Event::Insert(Insert(3))),
+                    Err(TransitionError::Rejected("locked"))
+                );
+                assert_eq!(machine.state(), &State::Idle(Idle { locked: true }));
+                assert_eq!(machine.context(), &Ctx { coins: 0 });
+
+                assert_eq!(machine.event(Event::Lock(Lock)), Ok(true));
+                assert_eq!(machine.event(Event::Insert(Insert(1))), Ok(true));
+                assert_eq!(machine.state(), &State::Refunding(Refunding));
+                assert_eq!(machine.event(Event::Take(Take)), Ok(true));
+
+                assert_eq!(machine.event(Event::Insert(Insert(9))), Ok(true));
+                assert_eq!(
+                    machine.event(Event::Take(Take)),
+                    Err(TransitionError::Rejected(
+                        "exit() returned a state the event does not move to"
+                    ))
+                );
+                assert_eq!(machine.state(), &State::Vending(Vending { jam: true }));
+                assert_eq!(machine.context(), &Ctx { coins: 10 });
+            }
+        }
+    };
+}
+
+vending!(by_match, Backend = Match;);
+vending!(by_table, Backend = Table;);
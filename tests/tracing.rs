@@ -0,0 +1,138 @@
+//! With the `tracing` feature, `event()` runs in an `fsm::event` span whose
+//! `machine`, `event`, `from` and `to` fields say what it did, without the
+//! crate depending on `tracing` itself.
+
+#![cfg(feature = "tracing")]
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use fsm_rs::fsm;
+use tracing::{
+    field::{Field, Visit},
+    span, Event as TracingEvent, Metadata, Subscriber,
+};
+
+#[derive(Default)]
+pub struct Door;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Closed;
+
+impl Closed {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Open(Open))
+    }
+
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Open;
+
+impl Open {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Err("blocked")
+    }
+
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Push;
+
+impl Push {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+fsm! {
+    Context = Door;
+
+    States {
+        Closed = Closed,
+        Open = Open
+    }
+
+    Events {
+        Push = Push
+    }
+
+    Transitions {
+        Push [ Closed => Open, Open => Closed ]
+    }
+}
+
+/// Records the names and fields of the spans, and the messages and fields of
+/// the events.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+struct Fields<'a>(&'a mut Vec<String>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0.push(format!("{:?}", value));
+        } else {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        let mut recorded = self.0.lock().unwrap();
+        recorded.push(span.metadata().name().to_string());
+        span.record(&mut Fields(&mut recorded));
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, values: &span::Record<'_>) {
+        values.record(&mut Fields(&mut self.0.lock().unwrap()));
+    }
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &TracingEvent<'_>) {
+        event.record(&mut Fields(&mut self.0.lock().unwrap()));
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[test]
+fn test_tracing() {
+    let recorder = Recorder::default();
+    let mut machine = Machine::new();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        assert_eq!(machine.event(Event::Push(Push)), Ok(true));
+        assert!(machine.event(Event::Push(Push)).is_err());
+    });
+
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        vec![
+            "fsm::event",
+            "machine=\"Door\"",
+            "event=\"Push\"",
+            "from=\"Closed\"",
+            "to=\"Open\"",
+            "transition",
+            "fsm::event",
+            "machine=\"Door\"",
+            "event=\"Push\"",
+            "from=\"Open\"",
+            "state exit failed",
+            "error=\"blocked\"",
+        ]
+    );
+}
@@ -1,5 +1,5 @@
 #[macro_use]
-extern crate machine;
+extern crate fsm_rs;
 
 machine!(
     #[derive(Clone, Debug, PartialEq)]
@@ -0,0 +1,118 @@
+//! With `Transactional;`, an `event()` call whose target state fails to
+//! enter leaves the state, the context and the history as they were.
+
+use fsm_rs::{fsm, TransitionError};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Wallet {
+    pub coins: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Locked {
+    pub tries: u32,
+}
+
+impl Locked {
+    fn on_coin(&mut self, wallet: &mut Wallet, coin: Coin) -> LockedCoinTarget {
+        self.tries += 1;
+        wallet.coins += coin.0;
+        if coin.0 > 10 {
+            LockedCoinTarget::Jammed(Jammed)
+        } else {
+            LockedCoinTarget::Open(Open)
+        }
+    }
+
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Open(Open))
+    }
+
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Open;
+
+impl Open {
+    fn exit(&mut self) -> Result<State, &'static str> {
+        Ok(State::Locked(Locked::default()))
+    }
+
+    fn entry(&mut self) {}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Jammed;
+
+impl Jammed {
+    fn entry(&mut self) -> Result<(), &'static str> {
+        Err("coin stuck")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Coin(pub u32);
+
+impl Coin {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Push;
+
+impl Push {
+    fn on(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+fsm! {
+    Context = Wallet;
+
+    History = 4;
+    Transactional;
+
+    States {
+        Locked = Locked,
+        Open = Open,
+        Jammed = Jammed
+    }
+
+    Final { Jammed }
+
+    Events {
+        Coin = Coin,
+        Push = Push
+    }
+
+    Transitions {
+        Coin [ Locked => Open, Locked => Jammed ],
+        Push [ Open => Locked ]
+    }
+}
+
+#[test]
+fn test_failed_entry_rolls_back() {
+    let mut machine = Machine::new();
+    assert_eq!(machine.event(Event::Coin(Coin(1))), Ok(true));
+    assert_eq!(machine.event(Event::Push(Push)), Ok(true));
+    assert_eq!(machine.event(Event::Coin(Coin(2))), Ok(true));
+    assert_eq!(machine.event(Event::Push(Push)), Ok(true));
+
+    let history: Vec<TransitionRecord> = machine.history().iter().copied().collect();
+    assert_eq!(history.len(), 4);
+
+    assert_eq!(
+        machine.event(Event::Coin(Coin(20))),
+        Err(TransitionError::Rejected("coin stuck"))
+    );
+    assert_eq!(machine.state(), &State::Locked(Locked { tries: 0 }));
+    assert_eq!(machine.context(), &Wallet { coins: 3 });
+    assert_eq!(
+        machine.history().iter().copied().collect::<Vec<_>>(),
+        history
+    );
+}
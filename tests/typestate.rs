@@ -0,0 +1,109 @@
+//! `Mode = Typestate;` gives each state its own `Machine<S>` with one method
+//! per transition. The `on_<event>` handlers do all the work: events need no
+//! `on()` guard and states no `entry()` or `exit()` hooks.
+
+use fsm_rs::fsm;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Kettle {
+    pub boils: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cold;
+
+impl Cold {
+    fn on_switch(self, _kettle: &mut Kettle, switch: Switch) -> Heating {
+        Heating {
+            target: switch.0,
+            temperature: 20,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heating {
+    pub target: u8,
+    pub temperature: u8,
+}
+
+impl Heating {
+    fn on_tick(&mut self, tick: &Tick) {
+        self.temperature += tick.0;
+    }
+
+    fn on_switch(self, kettle: &mut Kettle, _switch: Switch) -> HeatingSwitchTarget {
+        if self.temperature >= self.target {
+            kettle.boils += 1;
+            HeatingSwitchTarget::Boiled(Boiled(self.temperature))
+        } else {
+            HeatingSwitchTarget::Cold(Cold)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Boiled(u8);
+
+impl From<Boiled> for u8 {
+    fn from(boiled: Boiled) -> Self {
+        boiled.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Switch(u8);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tick(u8);
+
+fsm! {
+    Context = Kettle;
+
+    Output = u8;
+    Mode = Typestate;
+
+    States {
+        Cold = Cold,
+        Heating = Heating,
+        Boiled = Boiled
+    }
+
+    Final { Boiled }
+
+    Events {
+        Switch = Switch,
+        Tick = Tick
+    }
+
+    Transitions {
+        Switch [ Cold => Heating, Heating => Cold, Heating => Boiled ],
+        Tick [ Heating ~> Heating ]
+    }
+}
+
+#[test]
+fn test_typestate_walk() {
+    let mut machine = Machine::new().switch(Switch(100));
+    machine.tick(&Tick(50));
+    assert_eq!(
+        machine.state(),
+        &Heating {
+            target: 100,
+            temperature: 70,
+        }
+    );
+
+    let mut machine = match machine.switch(Switch(0)) {
+        HeatingSwitch::Cold(machine) => machine.switch(Switch(90)),
+        HeatingSwitch::Boiled(_) => panic!("70 degrees is not boiling"),
+    };
+    machine.tick(&Tick(75));
+
+    let machine = match machine.switch(Switch(0)) {
+        HeatingSwitch::Boiled(machine) => machine,
+        HeatingSwitch::Cold(_) => panic!("95 degrees is boiling"),
+    };
+    assert_eq!(machine.context(), &Kettle { boils: 1 });
+    assert_eq!(machine.into_output(), 95);
+}